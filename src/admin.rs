@@ -0,0 +1,38 @@
+//! Changes to the replays stored on the server, for servers that allow them. Requests carry
+//! the admin token from the settings, if one is set.
+
+use crate::{http, ratelimit};
+use reqwest::blocking::RequestBuilder;
+
+/// Adds the admin token, if one is set, as a bearer token.
+fn authorize(request: RequestBuilder, token: &str) -> RequestBuilder {
+    if token.is_empty() {
+        request
+    } else {
+        request.bearer_auth(token)
+    }
+}
+
+/// Deletes a replay with `DELETE /replay/{id}`.
+pub fn delete(server_addr: &str, replay_id: &str, token: &str) -> Result<(), String> {
+    ratelimit::SERVER.wait();
+    let request = http::client(server_addr).delete(format!("{}/replay/{}", server_addr, replay_id));
+    authorize(request, token)
+        .send()
+        .and_then(|resp| resp.error_for_status())
+        .map(drop)
+        .map_err(|err| err.to_string())
+}
+
+/// Renames a replay with `PATCH /replay/{id}` and a `{"friendlyName": …}` body.
+pub fn rename(server_addr: &str, replay_id: &str, name: &str, token: &str) -> Result<(), String> {
+    ratelimit::SERVER.wait();
+    let request = http::client(server_addr)
+        .patch(format!("{}/replay/{}", server_addr, replay_id))
+        .json(&serde_json::json!({ "friendlyName": name }));
+    authorize(request, token)
+        .send()
+        .and_then(|resp| resp.error_for_status())
+        .map(drop)
+        .map_err(|err| err.to_string())
+}
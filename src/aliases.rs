@@ -0,0 +1,70 @@
+use crate::{error, paths};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+/// Local nicknames for user ids, shared by all profiles and stored as JSON next to the confy file.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Aliases {
+    names: BTreeMap<String, String>,
+}
+
+impl Aliases {
+    fn path() -> Option<PathBuf> {
+        let config_path = paths::config_file(None).ok()?;
+        Some(config_path.parent()?.join("aliases.json"))
+    }
+
+    /// Loads the aliases, returning an empty set if the file is missing or unreadable.
+    pub fn load() -> Self {
+        let Some(path) = Self::path() else {
+            return Self::default();
+        };
+        match fs::read_to_string(&path) {
+            Ok(text) => serde_json::from_str(&text).unwrap_or_else(|err| {
+                error::report(format!("Error parsing aliases {}", path.display()), err);
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    /// Writes the aliases to disk.
+    pub fn save(&self) -> Result<(), String> {
+        let path = Self::path().ok_or("Could not determine aliases path")?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|err| err.to_string())?;
+        }
+        let text = serde_json::to_string_pretty(self).map_err(|err| err.to_string())?;
+        fs::write(&path, text).map_err(|err| format!("{}: {}", path.display(), err))
+    }
+
+    pub fn get(&self, user: &str) -> Option<&str> {
+        self.names.get(user).map(String::as_str)
+    }
+
+    /// Sets the nickname of a user; an empty name removes it.
+    pub fn set(&mut self, user: &str, name: &str) {
+        let name = name.trim();
+        if name.is_empty() {
+            self.names.remove(user);
+        } else {
+            self.names.insert(user.to_owned(), name.to_owned());
+        }
+    }
+
+    /// The nickname of a user, or the user id if there is none.
+    pub fn display(&self, user: &str) -> String {
+        self.get(user).unwrap_or(user).to_owned()
+    }
+
+    /// Whether the user id or its nickname contains `needle` (nicknames case-insensitively).
+    pub fn matches(&self, user: &str, needle: &str) -> bool {
+        user.contains(needle)
+            || self
+                .get(user)
+                .is_some_and(|name| name.to_lowercase().contains(&needle.to_lowercase()))
+    }
+}
@@ -0,0 +1,111 @@
+//! Starting the app with the user's session: a `Run` registry value on Windows, an XDG
+//! autostart entry on Linux. Other platforms report autostart as unsupported.
+
+use crate::paths;
+use std::path::PathBuf;
+
+/// Name of the registry value and the autostart entry.
+const NAME: &str = "LocalPavTV_GUI";
+
+/// Arguments that start this executable with profile `config_name` and the current portable mode.
+pub fn profile_arguments(config_name: Option<&str>) -> Vec<String> {
+    let mut args = Vec::new();
+    if let Some(profile) = config_name {
+        args.extend(["--profile".to_owned(), profile.to_owned()]);
+    }
+    if paths::portable_dir().is_some() {
+        args.push("--portable".to_owned());
+    }
+    args
+}
+
+/// Command line running this executable with `args`, with every part quoted.
+pub fn command_line(args: impl IntoIterator<Item = String>) -> Result<String, String> {
+    let exe = std::env::current_exe().map_err(|err| err.to_string())?;
+    let parts = std::iter::once(exe.display().to_string()).chain(args);
+    Ok(parts.map(|part| format!("\"{}\"", part)).collect::<Vec<_>>().join(" "))
+}
+
+/// Command line to register: hidden to the tray, with the same profile and portable mode as now.
+fn startup_command(config_name: Option<&str>) -> Result<String, String> {
+    command_line(std::iter::once("--minimized".to_owned()).chain(profile_arguments(config_name)))
+}
+
+#[cfg(windows)]
+const RUN_KEY: &str = r"HKCU\Software\Microsoft\Windows\CurrentVersion\Run";
+
+/// Runs `reg.exe` without flashing a console window.
+#[cfg(windows)]
+pub fn reg(args: &[&str]) -> std::io::Result<std::process::Output> {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+    std::process::Command::new("reg").args(args).creation_flags(CREATE_NO_WINDOW).output()
+}
+
+#[cfg(windows)]
+pub fn is_enabled() -> Result<bool, String> {
+    let output = reg(&["query", RUN_KEY, "/v", NAME]).map_err(|err| err.to_string())?;
+    Ok(output.status.success())
+}
+
+#[cfg(windows)]
+pub fn set(enabled: bool, config_name: Option<&str>) -> Result<(), String> {
+    let output = if enabled {
+        let command = startup_command(config_name)?;
+        reg(&["add", RUN_KEY, "/v", NAME, "/t", "REG_SZ", "/d", command.as_str(), "/f"])
+    } else {
+        reg(&["delete", RUN_KEY, "/v", NAME, "/f"])
+    }
+    .map_err(|err| err.to_string())?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_owned())
+    }
+}
+
+/// `$XDG_CONFIG_HOME/autostart/LocalPavTV_GUI.desktop`, falling back to `~/.config`.
+#[cfg(target_os = "linux")]
+fn desktop_file() -> Result<PathBuf, String> {
+    let config = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .ok_or("Neither XDG_CONFIG_HOME nor HOME is set")?;
+    Ok(config.join("autostart").join(format!("{}.desktop", NAME)))
+}
+
+#[cfg(target_os = "linux")]
+pub fn is_enabled() -> Result<bool, String> {
+    Ok(desktop_file()?.exists())
+}
+
+#[cfg(target_os = "linux")]
+pub fn set(enabled: bool, config_name: Option<&str>) -> Result<(), String> {
+    let path = desktop_file()?;
+    let result = if enabled {
+        let entry = format!(
+            "[Desktop Entry]\nType=Application\nName={}\nExec={}\nX-GNOME-Autostart-enabled=true\n",
+            NAME,
+            startup_command(config_name)?
+        );
+        path.parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|_| std::fs::write(&path, entry))
+    } else {
+        match std::fs::remove_file(&path) {
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            result => result,
+        }
+    };
+    result.map_err(|err| format!("{}: {}", path.display(), err))
+}
+
+#[cfg(not(any(windows, target_os = "linux")))]
+pub fn is_enabled() -> Result<bool, String> {
+    Err("Autostart is not supported on this platform".to_owned())
+}
+
+#[cfg(not(any(windows, target_os = "linux")))]
+pub fn set(_enabled: bool, _config_name: Option<&str>) -> Result<(), String> {
+    Err("Autostart is not supported on this platform".to_owned())
+}
@@ -0,0 +1,183 @@
+use crate::{error, paths, ratelimit};
+use eframe::egui;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Where avatars come from unless the settings say otherwise; `{user}` is replaced by the user id.
+pub const DEFAULT_URL_TEMPLATE: &str = "http://prod.cdn.pavlov-vr.com/avatar/{user}.png";
+
+/// Cached avatars older than this are downloaded again (but still used if the CDN is unreachable).
+const MAX_AGE: Duration = Duration::from_secs(7 * 86400);
+
+/// Directory holding one image per user id, next to the confy file.
+fn dir() -> Option<PathBuf> {
+    let config_path = paths::config_file(None).ok()?;
+    Some(config_path.parent()?.join("avatar_cache"))
+}
+
+fn path(user: &str) -> Option<PathBuf> {
+    let name: String = user
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+    // The bytes are stored as downloaded, so the format depends on the avatar source.
+    Some(dir()?.join(format!("{}.avatar", name)))
+}
+
+/// Decodes an avatar in any format the image crate detects (PNG, JPEG, WebP, …) and shrinks it to fit `max_px` × `max_px`, so the texture is no
+/// bigger than what is actually drawn.
+fn decode(bytes: &[u8], max_px: u32) -> Result<egui::ColorImage, String> {
+    let mut img = image::load_from_memory(bytes).map_err(|err| err.to_string())?;
+    if img.width() > max_px || img.height() > max_px {
+        img = img.resize(max_px, max_px, image::imageops::FilterType::Triangle);
+    }
+    let img = img.to_rgba8();
+    let size = [img.width() as usize, img.height() as usize];
+    Ok(egui::ColorImage::from_rgba_unmultiplied(size, &img.into_raw()))
+}
+
+fn download(user: &str, url_template: &str) -> Result<Vec<u8>, String> {
+    let client = reqwest::blocking::Client::builder()
+        .timeout(None)
+        .build()
+        .map_err(|err| err.to_string())?;
+    let url = url_template.replace("{user}", user);
+    ratelimit::CDN.wait();
+    let bytes = client
+        .get(&url)
+        .send()
+        .and_then(|resp| resp.error_for_status())
+        .and_then(|resp| resp.bytes())
+        .map_err(|err| err.to_string())?;
+    Ok(bytes.to_vec())
+}
+
+/// Loads the avatar of a user: a fresh copy from disk if there is one, otherwise from
+/// `url_template` (caching the bytes), falling back to a stale disk copy if the download fails.
+/// The image is downscaled to at most `max_px` pixels per side. Blocks, so call it from
+/// a background thread.
+pub fn load(user: &str, url_template: &str, max_px: u32) -> Result<egui::ColorImage, String> {
+    let path = path(user);
+    let cached = path.as_ref().and_then(|p| {
+        let age = fs::metadata(p).ok()?.modified().ok()?.elapsed().unwrap_or_default();
+        Some((fs::read(p).ok()?, age))
+    });
+    if let Some((bytes, age)) = &cached {
+        if *age < MAX_AGE {
+            if let Ok(image) = decode(bytes, max_px) {
+                return Ok(image);
+            }
+        }
+    }
+    match download(user, url_template).and_then(|bytes| decode(&bytes, max_px).map(|image| (bytes, image))) {
+        Ok((bytes, image)) => {
+            if let Some(path) = &path {
+                let written = path.parent().map_or(Ok(()), fs::create_dir_all).and_then(|_| fs::write(path, &bytes));
+                if let Err(err) = written {
+                    error::report(format!("Error caching avatar {}", path.display()), err);
+                }
+            }
+            Ok(image)
+        }
+        Err(err) => match cached {
+            Some((bytes, _)) => decode(&bytes, max_px),
+            None => Err(err),
+        },
+    }
+}
+
+/// Deletes every cached avatar so they are downloaded again.
+pub fn clear_cache() -> Result<(), String> {
+    match dir() {
+        Some(dir) if dir.exists() => fs::remove_dir_all(&dir).map_err(|err| format!("{}: {}", dir.display(), err)),
+        _ => Ok(()),
+    }
+}
+
+/// Deterministic stand-in for users without a usable avatar: a symmetric 5×5 pattern
+/// in a color derived from the user id, rendered at 64×64 like the CDN avatars.
+pub fn identicon(user: &str) -> egui::ColorImage {
+    // FNV-1a, so the pattern is stable across runs and platforms.
+    let hash = user.bytes().fold(0xcbf2_9ce4_8422_2325u64, |h, b| (h ^ b as u64).wrapping_mul(0x0100_0000_01b3));
+    let color = egui::Color32::from_rgb(
+        64 + (hash >> 40) as u8 % 160,
+        64 + (hash >> 48) as u8 % 160,
+        64 + (hash >> 56) as u8 % 160,
+    );
+    let background = egui::Color32::from_gray(235);
+    const SIZE: usize = 64;
+    const CELL: usize = 12;
+    const MARGIN: usize = (SIZE - 5 * CELL) / 2;
+    let mut image = egui::ColorImage::new([SIZE, SIZE], background);
+    for row in 0..5 {
+        for col in 0..3 {
+            if (hash >> (row * 3 + col)) & 1 == 0 {
+                continue;
+            }
+            // Mirror the left three columns onto the right.
+            for c in [col, 4 - col] {
+                for y in MARGIN + row * CELL..MARGIN + (row + 1) * CELL {
+                    for x in MARGIN + c * CELL..MARGIN + (c + 1) * CELL {
+                        image.pixels[y * SIZE + x] = color;
+                    }
+                }
+            }
+        }
+    }
+    image
+}
+
+/// Avatar textures with a maximum entry count. When full, the least recently drawn
+/// texture is freed; it is reloaded (from the disk cache) the next time it is shown.
+pub struct TextureCache {
+    textures: HashMap<String, (egui::TextureHandle, u64)>,
+    /// Incremented on every access, used as the recency stamp.
+    tick: u64,
+    capacity: usize,
+}
+
+impl TextureCache {
+    pub fn new(capacity: usize) -> Self {
+        Self { textures: HashMap::new(), tick: 0, capacity }
+    }
+
+    /// Returns the texture of a user and marks it as recently used.
+    pub fn get(&mut self, user: &str) -> Option<egui::TextureHandle> {
+        self.tick += 1;
+        let (texture, used) = self.textures.get_mut(user)?;
+        *used = self.tick;
+        Some(texture.clone())
+    }
+
+    pub fn insert(&mut self, user: String, texture: egui::TextureHandle) {
+        self.tick += 1;
+        self.textures.insert(user, (texture, self.tick));
+        self.evict();
+    }
+
+    pub fn set_capacity(&mut self, capacity: usize) {
+        if capacity != self.capacity {
+            self.capacity = capacity;
+            self.evict();
+        }
+    }
+
+    fn evict(&mut self) {
+        while self.textures.len() > self.capacity.max(1) {
+            let Some(oldest) = self.textures.iter().min_by_key(|(_, (_, used))| *used).map(|(user, _)| user.clone()) else {
+                break;
+            };
+            self.textures.remove(&oldest);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.textures.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.textures.clear();
+    }
+}
@@ -0,0 +1,62 @@
+use crate::{http, ratelimit};
+use serde::Deserialize;
+use std::time::Duration;
+
+/// What the connected server supports. Servers that have a `/version` endpoint answer
+/// `{"version": "…", "features": ["check", "limit", …]}`; older ones have none and get
+/// the feature set of the original LocalPavTV server.
+#[derive(Debug, Clone)]
+pub struct Capabilities {
+    /// Version string reported by the server; empty if it reports none.
+    pub version: String,
+    /// `/check/{id}` tells whether a replay was already downloaded to the server.
+    pub check: bool,
+    /// `/list` honors the `limit` query parameter.
+    pub list_limit: bool,
+    /// `/upload` accepts replay files POSTed as multipart forms.
+    pub upload: bool,
+    /// `DELETE /replay/{id}` removes a replay.
+    pub delete: bool,
+    /// `PATCH /replay/{id}` changes the friendly name of a replay.
+    pub rename: bool,
+}
+
+impl Default for Capabilities {
+    /// The original server: `/check` exists, `/list` pages have a fixed size, nothing can be uploaded, deleted or renamed.
+    fn default() -> Self {
+        Self { version: String::new(), check: true, list_limit: false, upload: false, delete: false, rename: false }
+    }
+}
+
+#[derive(Deserialize)]
+struct VersionResponse {
+    #[serde(default)]
+    version: String,
+    #[serde(default)]
+    features: Vec<String>,
+}
+
+/// Reads `/version` from a server, falling back to the original server's capabilities
+/// when the endpoint is missing or unreadable.
+pub fn detect(server_addr: &str) -> Capabilities {
+    ratelimit::SERVER.wait();
+    let response = http::builder(server_addr)
+        .timeout(Duration::from_secs(10))
+        .build()
+        .and_then(|client| client.get(format!("{}/version", server_addr)).send())
+        .and_then(|resp| resp.error_for_status());
+    let Ok(response) = response else {
+        return Capabilities::default();
+    };
+    let Ok(text) = response.text() else {
+        return Capabilities::default();
+    };
+    match serde_json::from_str::<VersionResponse>(&text) {
+        Ok(parsed) => {
+            let has = |feature: &str| parsed.features.iter().any(|f| f == feature);
+            Capabilities { check: has("check"), list_limit: has("limit"), upload: has("upload"), delete: has("delete"), rename: has("rename"), version: parsed.version }
+        }
+        // A bare version string: an older server without a feature list.
+        Err(_) => Capabilities { version: text.trim().to_owned(), ..Capabilities::default() },
+    }
+}
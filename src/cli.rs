@@ -0,0 +1,89 @@
+use clap::Parser;
+use std::num::NonZeroUsize;
+use std::path::PathBuf;
+
+/// Command-line flags. Every flag can also be supplied through its environment
+/// variable, and both take precedence over the values stored in the confy file.
+#[derive(Debug, Default, Clone, Parser)]
+#[command(name = "LocalPavTV_GUI", version, about = "Browse and archive replays from a LocalPavTV server")]
+pub struct Cli {
+    /// Replay server address, e.g. http://server:3000.
+    #[arg(long, env = "LOCALPAVTV_SERVER")]
+    pub server: Option<String>,
+    /// Name of the confy configuration profile to load and save.
+    #[arg(long, env = "LOCALPAVTV_PROFILE")]
+    pub profile: Option<String>,
+    /// Settings file to load and save instead of the profile's confy file.
+    #[arg(long, env = "LOCALPAVTV_CONFIG")]
+    pub config: Option<PathBuf>,
+    /// Keep config, caches and libraries in a folder beside the executable
+    /// (also enabled by a `portable.txt` file there).
+    #[arg(long, env = "LOCALPAVTV_PORTABLE")]
+    pub portable: bool,
+    /// Auto‑refresh interval in seconds.
+    #[arg(long, env = "LOCALPAVTV_REFRESH_INTERVAL")]
+    pub refresh_interval: Option<u64>,
+    /// Enable or disable auto‑refresh (true/false).
+    #[arg(long, env = "LOCALPAVTV_AUTO_REFRESH")]
+    pub auto_refresh: Option<bool>,
+    /// Auto‑download filter matched against user ids, workshop mods and workshop id.
+    #[arg(long, env = "LOCALPAVTV_AUTO_DOWNLOAD_FILTER")]
+    pub auto_download_filter: Option<String>,
+    /// Ask the server to download this replay at startup.
+    #[arg(long, env = "LOCALPAVTV_DOWNLOAD")]
+    pub download: Option<String>,
+    /// Run without a window: with `--download`, download that replay and exit; with `--page`,
+    /// print that page of the list (id, created, game mode, name; tab-separated) and exit;
+    /// otherwise refresh and auto-download in a loop, e.g. as a service. The loop does not start
+    /// while the window runs on the same profile, and `--download` is passed on to it.
+    #[arg(long, env = "LOCALPAVTV_HEADLESS")]
+    pub headless: bool,
+    /// Also append headless log lines to this file. Without a console to print to, they go to
+    /// `<profile>.headless.log` next to the config file by default.
+    #[arg(long, env = "LOCALPAVTV_LOG_FILE")]
+    pub log_file: Option<PathBuf>,
+    /// Page of the replay list to start on (1 = newest).
+    #[arg(long, env = "LOCALPAVTV_PAGE")]
+    pub page: Option<NonZeroUsize>,
+    /// Start hidden to the tray (minimized if there is no tray), as autostart does.
+    #[arg(long, env = "LOCALPAVTV_MINIMIZED")]
+    pub minimized: bool,
+    /// `pavlovtv://download/<replay_id>?server=<address>` link to download, passed on to the
+    /// running instance if there is one.
+    pub link: Option<String>,
+}
+
+impl Cli {
+    /// Applies the overrides that were given on top of settings loaded from disk.
+    pub fn apply(&self, settings: &mut crate::Settings) {
+        if let Some(server) = &self.server {
+            settings.server_addr = server.clone();
+        }
+        if let Some(refresh_interval) = self.refresh_interval {
+            settings.refresh_interval = refresh_interval;
+        }
+        if let Some(auto_refresh) = self.auto_refresh {
+            settings.auto_refresh = auto_refresh;
+        }
+        if let Some(filter) = &self.auto_download_filter {
+            settings.auto_download_filter = filter.clone();
+        }
+    }
+
+    /// Undoes `apply` before settings are written to disk, so overrides only last for this
+    /// run. `originals` are the settings as loaded; values edited since then are kept.
+    pub fn restore(&self, settings: &mut crate::Settings, originals: &crate::Settings) {
+        if self.server.as_ref() == Some(&settings.server_addr) {
+            settings.server_addr = originals.server_addr.clone();
+        }
+        if self.refresh_interval == Some(settings.refresh_interval) {
+            settings.refresh_interval = originals.refresh_interval;
+        }
+        if self.auto_refresh == Some(settings.auto_refresh) {
+            settings.auto_refresh = originals.auto_refresh;
+        }
+        if self.auto_download_filter.as_ref() == Some(&settings.auto_download_filter) {
+            settings.auto_download_filter = originals.auto_download_filter.clone();
+        }
+    }
+}
@@ -0,0 +1,25 @@
+//! Console output on Windows. The executable is built for the Windows subsystem, so it
+//! starts without a console and `println!` goes nowhere unless one is attached.
+
+/// Makes printed lines reach the console of the shell that started the app, if there is
+/// one. Returns whether output goes anywhere: a console, or a file or pipe it was
+/// redirected to. Always true on other platforms.
+#[cfg(windows)]
+pub fn attach() -> bool {
+    use winapi::um::handleapi::INVALID_HANDLE_VALUE;
+    use winapi::um::processenv::GetStdHandle;
+    use winapi::um::winbase::STD_OUTPUT_HANDLE;
+    use winapi::um::wincon::{AttachConsole, ATTACH_PARENT_PROCESS};
+
+    // Redirected output already has somewhere to go.
+    let stdout = unsafe { GetStdHandle(STD_OUTPUT_HANDLE) };
+    if !stdout.is_null() && stdout != INVALID_HANDLE_VALUE {
+        return true;
+    }
+    unsafe { AttachConsole(ATTACH_PARENT_PROCESS) != 0 }
+}
+
+#[cfg(not(windows))]
+pub fn attach() -> bool {
+    true
+}
@@ -0,0 +1,79 @@
+use crate::paths;
+use std::backtrace::Backtrace;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Directory holding crash reports, next to the confy file.
+fn dir() -> Option<PathBuf> {
+    let config_path = paths::config_file(None).ok()?;
+    Some(config_path.parent()?.join("crash_reports"))
+}
+
+/// File naming the report of a crash the user has not been told about yet.
+fn marker() -> Option<PathBuf> {
+    Some(dir()?.join("unseen"))
+}
+
+/// Installs a panic hook that writes the panic message, a backtrace and the app version to
+/// `crash_reports/crash-<unix time>.txt`, so a windowed build does not die silently. The
+/// default hook still runs afterwards. Call after `paths::init`.
+pub fn install() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let thread = std::thread::current();
+        let report = format!(
+            "LocalPavTV_GUI {} ({} {})\nThread: {}\n\n{}\n\nBacktrace:\n{}\n",
+            env!("CARGO_PKG_VERSION"),
+            std::env::consts::OS,
+            std::env::consts::ARCH,
+            thread.name().unwrap_or("unnamed"),
+            info,
+            Backtrace::force_capture()
+        );
+        match write(&report) {
+            Ok(path) => eprintln!("Crash report written to {}", path.display()),
+            Err(err) => eprintln!("Error writing crash report: {}", err),
+        }
+        default_hook(info);
+    }));
+}
+
+fn write(report: &str) -> io::Result<PathBuf> {
+    let (Some(dir), Some(marker)) = (dir(), marker()) else {
+        return Err(io::Error::new(io::ErrorKind::NotFound, "no config directory"));
+    };
+    fs::create_dir_all(&dir)?;
+    let path = dir.join(format!("crash-{}.txt", crate::unix_now()));
+    fs::write(&path, report)?;
+    fs::write(marker, path.display().to_string())?;
+    Ok(path)
+}
+
+/// Report of a crash since the last call to `acknowledge`, if its file still exists.
+pub fn unseen() -> Option<PathBuf> {
+    let path = PathBuf::from(fs::read_to_string(marker()?).ok()?.trim());
+    path.exists().then_some(path)
+}
+
+/// Stops `unseen` from returning the last report.
+pub fn acknowledge() {
+    if let Some(marker) = marker() {
+        let _ = fs::remove_file(marker);
+    }
+}
+
+/// Opens a report in the default text viewer.
+pub fn open(path: &Path) -> io::Result<()> {
+    let mut command = if cfg!(windows) {
+        let mut command = Command::new("cmd");
+        command.args(["/C", "start", ""]);
+        command
+    } else if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else {
+        Command::new("xdg-open")
+    };
+    command.arg(path).spawn().map(|_| ())
+}
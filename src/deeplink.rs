@@ -0,0 +1,94 @@
+//! `pavlovtv://download/<replay_id>?server=<address>` links, which start the download of a
+//! replay in this app (or in the instance that is already running).
+
+#[cfg(any(windows, target_os = "linux"))]
+use crate::autostart;
+use reqwest::Url;
+
+pub const SCHEME: &str = "pavlovtv";
+
+/// A replay to download, taken from a link.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Link {
+    pub replay_id: String,
+    /// Server the replay is on; `None` uses the active server.
+    pub server: Option<String>,
+}
+
+/// Reads a `pavlovtv://download/<replay_id>` link, with an optional `server` query parameter.
+pub fn parse(link: &str) -> Result<Link, String> {
+    let url = Url::parse(link.trim()).map_err(|err| format!("`{}` is not a link: {}", link, err))?;
+    if url.scheme() != SCHEME {
+        return Err(format!("`{}` is not a {}:// link", link, SCHEME));
+    }
+    if url.host_str() != Some("download") {
+        return Err(format!("`{}` does not link to a download", link));
+    }
+    let replay_id = url.path().trim_matches('/').to_owned();
+    if replay_id.is_empty() || replay_id.contains('/') {
+        return Err(format!("`{}` does not name a replay", link));
+    }
+    let server = url
+        .query_pairs()
+        .find(|(name, _)| name == "server")
+        .map(|(_, server)| server.trim().trim_end_matches('/').to_owned())
+        .filter(|server| !server.is_empty());
+    Ok(Link { replay_id, server })
+}
+
+/// Makes the OS open `pavlovtv://` links with this executable and profile.
+#[cfg(windows)]
+pub fn register(config_name: Option<&str>) -> Result<(), String> {
+    let key = format!(r"HKCU\Software\Classes\{}", SCHEME);
+    let command = autostart::command_line(autostart::profile_arguments(config_name).into_iter().chain(["%1".to_owned()]))?;
+    let description = format!("URL:{} Protocol", SCHEME);
+    let command_key = format!(r"{}\shell\open\command", key);
+    let steps: [&[&str]; 3] = [
+        &["add", key.as_str(), "/ve", "/d", description.as_str(), "/f"],
+        &["add", key.as_str(), "/v", "URL Protocol", "/d", "", "/f"],
+        &["add", command_key.as_str(), "/ve", "/d", command.as_str(), "/f"],
+    ];
+    for args in steps {
+        let output = autostart::reg(args).map_err(|err| err.to_string())?;
+        if !output.status.success() {
+            return Err(String::from_utf8_lossy(&output.stderr).trim().to_owned());
+        }
+    }
+    Ok(())
+}
+
+/// Makes the OS open `pavlovtv://` links with this executable and profile: a desktop entry
+/// for the scheme in `$XDG_DATA_HOME/applications`, made the default with `xdg-mime`.
+#[cfg(target_os = "linux")]
+pub fn register(config_name: Option<&str>) -> Result<(), String> {
+    use std::path::PathBuf;
+    let data = std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))
+        .ok_or("Neither XDG_DATA_HOME nor HOME is set")?;
+    let file_name = format!("localpavtv_gui-{}.desktop", SCHEME);
+    let path = data.join("applications").join(&file_name);
+    let entry = format!(
+        "[Desktop Entry]\nType=Application\nName=LocalPavTV_GUI\nExec={} %u\nMimeType=x-scheme-handler/{}\nNoDisplay=true\n",
+        autostart::command_line(autostart::profile_arguments(config_name))?,
+        SCHEME
+    );
+    path.parent()
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|_| std::fs::write(&path, entry))
+        .map_err(|err| format!("{}: {}", path.display(), err))?;
+    let status = std::process::Command::new("xdg-mime")
+        .args(["default", &file_name, &format!("x-scheme-handler/{}", SCHEME)])
+        .status()
+        .map_err(|err| format!("xdg-mime: {}", err))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("xdg-mime failed ({})", status))
+    }
+}
+
+#[cfg(not(any(windows, target_os = "linux")))]
+pub fn register(_config_name: Option<&str>) -> Result<(), String> {
+    Err(format!("Registering {}:// links is not supported on this platform", SCHEME))
+}
@@ -0,0 +1,282 @@
+use crate::{http, ratelimit};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::time::{Duration, Instant};
+
+/// JSON type a field of the `/list` response is expected to have.
+#[derive(Clone, Copy, PartialEq)]
+enum Kind {
+    String,
+    Bool,
+    /// Non-negative integer.
+    Count,
+    StringArray,
+    Array,
+}
+
+impl Kind {
+    fn name(self) -> &'static str {
+        match self {
+            Kind::String => "string",
+            Kind::Bool => "boolean",
+            Kind::Count => "non-negative integer",
+            Kind::StringArray => "array of strings",
+            Kind::Array => "array",
+        }
+    }
+
+    fn matches(self, value: &Value) -> bool {
+        match self {
+            Kind::String => value.is_string(),
+            Kind::Bool => value.is_boolean(),
+            Kind::Count => value.is_u64(),
+            Kind::StringArray => value.as_array().is_some_and(|items| items.iter().all(Value::is_string)),
+            Kind::Array => value.is_array(),
+        }
+    }
+}
+
+/// Fields of the top-level `/list` response.
+const LIST_FIELDS: &[(&str, Kind)] = &[("replays", Kind::Array), ("total", Kind::Count)];
+
+/// Fields of each replay, mirroring `Replay`.
+const REPLAY_FIELDS: &[(&str, Kind)] = &[
+    ("_id", Kind::String),
+    ("shack", Kind::Bool),
+    ("workshop_mods", Kind::String),
+    ("workshop_id", Kind::String),
+    ("competitive", Kind::Bool),
+    ("gameMode", Kind::String),
+    ("created", Kind::String),
+    ("expires", Kind::String),
+    ("live", Kind::Bool),
+    ("friendlyName", Kind::String),
+    ("users", Kind::StringArray),
+    ("secondsSince", Kind::Count),
+    ("modcount", Kind::Count),
+];
+
+/// Other names `Replay` accepts for a field; keep in sync with its `serde(alias)` attributes.
+const REPLAY_ALIASES: &[(&str, &[&str])] = &[
+    ("_id", &["id"]),
+    ("workshop_mods", &["workshopMods"]),
+    ("workshop_id", &["workshopId"]),
+    ("gameMode", &["game_mode"]),
+    ("friendlyName", &["friendly_name", "name"]),
+    ("secondsSince", &["seconds_since"]),
+    ("modcount", &["mod_count"]),
+];
+
+/// Names a replay field may appear under, the expected one first.
+fn replay_names(name: &'static str) -> impl Iterator<Item = &'static str> {
+    let aliases = REPLAY_ALIASES.iter().find(|(field, _)| *field == name).map_or(&[][..], |(_, aliases)| *aliases);
+    std::iter::once(name).chain(aliases.iter().copied())
+}
+
+/// Replay fields that are absent under every accepted name, with the number of replays
+/// lacking each. The list still loads with defaults for them, so this is only reported.
+pub fn missing_fields(replays: &[Value]) -> Vec<(&'static str, usize)> {
+    REPLAY_FIELDS
+        .iter()
+        .filter_map(|(name, _)| {
+            let missing = replays
+                .iter()
+                .filter_map(Value::as_object)
+                .filter(|replay| !replay_names(name).any(|key| replay.contains_key(key)))
+                .count();
+            (missing > 0).then_some((*name, missing))
+        })
+        .collect()
+}
+
+/// Short description of a JSON value's type for the report.
+fn describe(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_u64() => "integer",
+        Value::Number(n) if n.is_i64() => "negative integer",
+        Value::Number(_) => "decimal number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// Problems found with one field, counted over all objects that were checked.
+#[derive(Default)]
+struct FieldIssues {
+    missing: usize,
+    /// Actual type → number of occurrences.
+    mistyped: BTreeMap<&'static str, usize>,
+}
+
+/// Checks one object against a field list and tallies missing, mistyped and unexpected fields.
+/// Fields under an accepted alias count as present.
+fn check_object(
+    object: &serde_json::Map<String, Value>,
+    fields: &[(&'static str, Kind)],
+    issues: &mut BTreeMap<String, FieldIssues>,
+    extra: &mut BTreeMap<String, usize>,
+) {
+    for (name, kind) in fields {
+        match replay_names(name).find_map(|key| object.get(key)) {
+            None => issues.entry(name.to_string()).or_default().missing += 1,
+            Some(value) if !kind.matches(value) => {
+                *issues.entry(name.to_string()).or_default().mistyped.entry(describe(value)).or_default() += 1;
+            }
+            Some(_) => {}
+        }
+    }
+    for key in object.keys() {
+        if !fields.iter().any(|(name, _)| replay_names(name).any(|n| n == key)) {
+            *extra.entry(key.clone()).or_default() += 1;
+        }
+    }
+}
+
+fn write_issues(
+    report: &mut String,
+    what: &str,
+    total: usize,
+    fields: &[(&str, Kind)],
+    issues: &BTreeMap<String, FieldIssues>,
+    extra: &BTreeMap<String, usize>,
+) {
+    for (name, field) in issues {
+        let expected = fields.iter().find(|(n, _)| n == name).map_or("?", |(_, kind)| kind.name());
+        if field.missing > 0 {
+            let _ = writeln!(report, "✗ {}: `{}` is missing in {} of {} (expected {})", what, name, field.missing, total, expected);
+        }
+        for (actual, count) in &field.mistyped {
+            let _ = writeln!(report, "✗ {}: `{}` is a {} in {} of {} (expected {})", what, name, actual, count, total, expected);
+        }
+    }
+    for (name, count) in extra {
+        let _ = writeln!(report, "• {}: unexpected field `{}` in {} of {} (ignored by this client)", what, name, count, total);
+    }
+}
+
+/// Fetches the first page of `/list` and describes every way it differs from the schema
+/// this client expects. A mistyped field makes the client skip that replay and a missing
+/// one leaves it with a default value, so those are the ones to fix; extra fields are harmless.
+pub fn validate(server_addr: &str) -> String {
+    let list_url = format!("{}/list?offset=0", server_addr);
+    let mut report = format!("Checked {}\n\n", list_url);
+    ratelimit::SERVER.wait();
+    let response = match http::client(server_addr).get(&list_url).send() {
+        Ok(response) => response,
+        Err(err) => {
+            let _ = writeln!(report, "✗ Request failed: {}", err);
+            return report;
+        }
+    };
+    let status = response.status();
+    if !status.is_success() {
+        let _ = writeln!(report, "✗ The server answered HTTP {}", status);
+        return report;
+    }
+    let body: Value = match response.json() {
+        Ok(body) => body,
+        Err(err) => {
+            let _ = writeln!(report, "✗ The response is not valid JSON: {}", err);
+            return report;
+        }
+    };
+    let Some(object) = body.as_object() else {
+        let _ = writeln!(report, "✗ The response is a {}, expected an object with `replays` and `total`", describe(&body));
+        return report;
+    };
+
+    let mut issues = BTreeMap::new();
+    let mut extra = BTreeMap::new();
+    check_object(object, LIST_FIELDS, &mut issues, &mut extra);
+    write_issues(&mut report, "response", 1, LIST_FIELDS, &issues, &extra);
+    let problems = !issues.is_empty();
+
+    let replays = object.get("replays").and_then(Value::as_array).map(Vec::as_slice).unwrap_or_default();
+    let mut replay_issues = BTreeMap::new();
+    let mut replay_extra = BTreeMap::new();
+    let mut not_objects = 0;
+    for replay in replays {
+        match replay.as_object() {
+            Some(replay) => check_object(replay, REPLAY_FIELDS, &mut replay_issues, &mut replay_extra),
+            None => not_objects += 1,
+        }
+    }
+    if not_objects > 0 {
+        let _ = writeln!(report, "✗ replays: {} of {} entries are not objects", not_objects, replays.len());
+    }
+    write_issues(&mut report, "replay", replays.len(), REPLAY_FIELDS, &replay_issues, &replay_extra);
+
+    if !problems && replay_issues.is_empty() && not_objects == 0 {
+        let _ = writeln!(report, "✓ The response matches the expected schema ({} replays checked).", replays.len());
+    } else {
+        let _ = writeln!(report, "\nFields marked ✗ make this client skip replays or show them incomplete.");
+    }
+    report
+}
+
+/// Result of probing a server for the status section.
+pub struct Probe {
+    pub list_url: String,
+    /// Round-trip time of the `/list` request, also when it failed.
+    pub latency: Duration,
+    /// HTTP status of `/list`, or the error if the server did not answer.
+    pub status: Result<u16, String>,
+    /// `total` of the first page, if the response parsed.
+    pub total: Option<u64>,
+    /// Storage-related fields from `/status`, for servers that expose one.
+    pub storage: Vec<(String, String)>,
+}
+
+/// Whether a `/status` field name looks like disk or space information.
+fn is_storage_field(name: &str) -> bool {
+    let name = name.to_lowercase();
+    ["disk", "space", "free", "storage", "size", "bytes"].iter().any(|word| name.contains(word))
+}
+
+/// Requests the first page of `/list` (timing it) and, if the server has one, `/status`.
+pub fn probe(server_addr: &str) -> Probe {
+    let client = http::builder(server_addr).timeout(Duration::from_secs(15)).build();
+    let list_url = format!("{}/list?offset=0", server_addr);
+    ratelimit::SERVER.wait();
+    let started = Instant::now();
+    let response = client.as_ref().map_err(|err| err.to_string()).and_then(|client| {
+        client.get(&list_url).send().map_err(|err| err.to_string())
+    });
+    let latency = started.elapsed();
+    let (status, total) = match response {
+        Ok(response) => {
+            let status = response.status().as_u16();
+            let total = response.json::<Value>().ok().and_then(|body| body.get("total")?.as_u64());
+            (Ok(status), total)
+        }
+        Err(err) => (Err(err), None),
+    };
+
+    let mut storage = Vec::new();
+    ratelimit::SERVER.wait();
+    let status_body = client
+        .ok()
+        .and_then(|client| client.get(format!("{}/status", server_addr)).send().ok())
+        .filter(|response| response.status().is_success())
+        .and_then(|response| response.json::<Value>().ok());
+    if let Some(Value::Object(fields)) = status_body {
+        for (name, value) in &fields {
+            match value {
+                Value::Object(inner) => {
+                    for (inner_name, inner_value) in inner {
+                        if is_storage_field(name) || is_storage_field(inner_name) {
+                            storage.push((format!("{}.{}", name, inner_name), inner_value.to_string()));
+                        }
+                    }
+                }
+                _ if is_storage_field(name) => storage.push((name.clone(), value.to_string())),
+                _ => {}
+            }
+        }
+    }
+    Probe { list_url, latency, status, total, storage }
+}
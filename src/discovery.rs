@@ -0,0 +1,116 @@
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::net::{Ipv4Addr, SocketAddr, TcpStream, UdpSocket};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Port LocalPavTV listens on unless configured otherwise.
+pub const DEFAULT_PORT: u16 = 3000;
+
+/// Datagram broadcast to `DEFAULT_PORT` over UDP. Servers that support discovery answer with
+/// `{"name": "…", "url": "http://…"}`; both fields are optional.
+const PROBE: &[u8] = b"LOCALPAVTV_DISCOVER";
+
+/// How long broadcast replies are collected.
+const LISTEN_TIME: Duration = Duration::from_secs(2);
+
+/// How long a connection attempt to a host of the subnet may take during the sweep.
+const CONNECT_TIMEOUT: Duration = Duration::from_millis(400);
+
+/// A server found on the local network.
+#[derive(Clone)]
+pub struct Found {
+    pub url: String,
+    /// Name the server announced, if it answered the broadcast.
+    pub name: String,
+    /// `total` of its replay list, if it could be read.
+    pub replays: Option<u64>,
+}
+
+/// Address of this machine on the LAN, found by routing a UDP socket (nothing is sent).
+fn local_ipv4() -> Option<Ipv4Addr> {
+    let socket = UdpSocket::bind("0.0.0.0:0").ok()?;
+    socket.connect("192.0.2.1:80").ok()?;
+    match socket.local_addr().ok()? {
+        SocketAddr::V4(addr) if !addr.ip().is_loopback() => Some(*addr.ip()),
+        _ => None,
+    }
+}
+
+/// Replay count of a server's first `/list` page, or `None` if it is not a LocalPavTV server.
+fn list_total(url: &str) -> Option<u64> {
+    let client = reqwest::blocking::Client::builder().timeout(Duration::from_secs(3)).build().ok()?;
+    let body: Value = client.get(format!("{}/list?offset=0", url)).send().ok()?.json().ok()?;
+    body.get("replays")?.as_array()?;
+    Some(body.get("total").and_then(Value::as_u64).unwrap_or(0))
+}
+
+/// Broadcasts the discovery probe and collects the servers that answer.
+fn broadcast() -> Vec<Found> {
+    let Ok(socket) = UdpSocket::bind("0.0.0.0:0") else {
+        return Vec::new();
+    };
+    if socket.set_broadcast(true).is_err() || socket.send_to(PROBE, (Ipv4Addr::BROADCAST, DEFAULT_PORT)).is_err() {
+        return Vec::new();
+    }
+    let _ = socket.set_read_timeout(Some(Duration::from_millis(200)));
+    let started = Instant::now();
+    let mut found = Vec::new();
+    let mut buffer = [0u8; 2048];
+    while started.elapsed() < LISTEN_TIME {
+        let Ok((len, from)) = socket.recv_from(&mut buffer) else {
+            continue;
+        };
+        let reply: Value = serde_json::from_slice(&buffer[..len]).unwrap_or(Value::Null);
+        let field = |name: &str| reply.get(name).and_then(Value::as_str).unwrap_or_default().to_owned();
+        let url = Some(field("url"))
+            .filter(|url| !url.is_empty())
+            .unwrap_or_else(|| format!("http://{}:{}", from.ip(), DEFAULT_PORT));
+        found.push(Found { url, name: field("name"), replays: None });
+    }
+    found
+}
+
+/// Tries `DEFAULT_PORT` on every host of this machine's /24 subnet, for servers that do
+/// not answer the broadcast.
+fn sweep() -> Vec<Found> {
+    let Some(ip) = local_ipv4() else {
+        return Vec::new();
+    };
+    let [a, b, c, _] = ip.octets();
+    let handles: Vec<_> = (1..=254u8)
+        .map(|d| {
+            thread::spawn(move || {
+                let addr = SocketAddr::from((Ipv4Addr::new(a, b, c, d), DEFAULT_PORT));
+                TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT).ok()?;
+                Some(format!("http://{}", addr))
+            })
+        })
+        .collect();
+    handles
+        .into_iter()
+        .filter_map(|handle| handle.join().ok().flatten())
+        .map(|url| Found { url, name: String::new(), replays: None })
+        .collect()
+}
+
+/// Looks for LocalPavTV servers on the local network, both by broadcast and by sweeping the
+/// subnet, and keeps the ones whose `/list` answers. Takes a few seconds.
+pub fn discover() -> Vec<Found> {
+    let broadcast = thread::spawn(broadcast);
+    let mut candidates: BTreeMap<String, Found> = sweep().into_iter().map(|found| (found.url.clone(), found)).collect();
+    // Announced names win over bare sweep hits for the same address.
+    for found in broadcast.join().unwrap_or_default() {
+        candidates.insert(found.url.clone(), found);
+    }
+    let checks: Vec<_> = candidates
+        .into_values()
+        .map(|mut found| {
+            thread::spawn(move || {
+                found.replays = Some(list_total(&found.url)?);
+                Some(found)
+            })
+        })
+        .collect();
+    checks.into_iter().filter_map(|handle| handle.join().ok().flatten()).collect()
+}
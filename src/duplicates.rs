@@ -0,0 +1,76 @@
+//! Replays that are probably the same match listed twice, e.g. recorded by two servers or
+//! listed again under another id. Every replay seen this session counts, so copies are
+//! found across pages and servers.
+
+use crate::{parse_timestamp, Replay};
+use std::collections::HashMap;
+
+/// Replays created at most this far apart can still be the same match.
+const WINDOW_SECONDS: i64 = 60;
+
+/// What copies of a match have in common besides their creation time.
+#[derive(PartialEq, Eq, Hash)]
+struct Key {
+    game_mode: String,
+    /// Sorted, so the order the server lists players in does not matter.
+    users: Vec<String>,
+}
+
+impl Key {
+    /// `None` for replays without players, which would all look alike.
+    fn of(replay: &Replay) -> Option<Self> {
+        if replay.users.is_empty() {
+            return None;
+        }
+        let mut users = replay.users.clone();
+        users.sort();
+        Some(Key { game_mode: replay.gameMode.clone(), users })
+    }
+}
+
+/// A replay as remembered by the index.
+#[derive(Clone)]
+pub struct Seen {
+    pub replay_id: String,
+    pub name: String,
+    pub server: String,
+    /// Unix timestamp of `created`.
+    created: i64,
+}
+
+#[derive(Default)]
+pub struct Index {
+    by_key: HashMap<Key, Vec<Seen>>,
+}
+
+impl Index {
+    /// Remembers a replay listed by `server`, replacing what was known about its id.
+    pub fn add(&mut self, replay: &Replay, server: &str) {
+        let (Some(key), Some(created)) = (Key::of(replay), parse_timestamp(&replay.created)) else {
+            return;
+        };
+        let seen = Seen { replay_id: replay._id.clone(), name: replay.friendlyName.clone(), server: server.to_owned(), created: created.timestamp() };
+        let copies = self.by_key.entry(key).or_default();
+        match copies.iter_mut().find(|other| other.replay_id == seen.replay_id) {
+            Some(existing) => *existing = seen,
+            None => copies.push(seen),
+        }
+    }
+
+    /// Other replays that look like the same match as `replay`, oldest first.
+    pub fn duplicates_of(&self, replay: &Replay) -> Vec<&Seen> {
+        let (Some(key), Some(created)) = (Key::of(replay), parse_timestamp(&replay.created)) else {
+            return Vec::new();
+        };
+        let created = created.timestamp();
+        let mut copies: Vec<&Seen> = self
+            .by_key
+            .get(&key)
+            .into_iter()
+            .flatten()
+            .filter(|other| other.replay_id != replay._id && (other.created - created).abs() <= WINDOW_SECONDS)
+            .collect();
+        copies.sort_by_key(|other| other.created);
+        copies
+    }
+}
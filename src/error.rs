@@ -0,0 +1,46 @@
+use std::fmt::Display;
+use std::sync::Mutex;
+
+/// Why a request to the replay server failed, with the URL it was for.
+#[derive(Debug, thiserror::Error)]
+pub enum AppError {
+    /// The request could not be sent or its body could not be read.
+    #[error("Error fetching {url}: {source}")]
+    Network {
+        url: String,
+        #[source]
+        source: reqwest::Error,
+    },
+    /// The server answered with an error status.
+    #[error("{url} answered HTTP {status}")]
+    Status { url: String, status: reqwest::StatusCode },
+    /// The response is not in the format this client expects.
+    #[error("Error parsing {url}: {message}")]
+    Parse { url: String, message: String },
+    /// Every server of the "All servers" view failed.
+    #[error("{}", .0.iter().map(ToString::to_string).collect::<Vec<_>>().join("; "))]
+    AllServers(Vec<AppError>),
+}
+
+impl AppError {
+    /// Maps a reqwest error of a request to `url`.
+    pub fn network(url: &str) -> impl FnOnce(reqwest::Error) -> Self + '_ {
+        move |source| AppError::Network { url: url.to_owned(), source }
+    }
+}
+
+/// Failures of background work nobody returns a result to, waiting for the activity feed.
+static REPORTED: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Reports a failure that has no caller to hand it to, e.g. a cache that could not be
+/// written. It is printed and shown in the activity feed and error banner.
+pub fn report(context: impl Display, err: impl Display) {
+    let message = format!("{}: {}", context, err);
+    eprintln!("{}", message);
+    REPORTED.lock().unwrap().push(message);
+}
+
+/// Takes the failures reported since the last call.
+pub fn take_reported() -> Vec<String> {
+    std::mem::take(&mut *REPORTED.lock().unwrap())
+}
@@ -0,0 +1,121 @@
+use crate::{error, unix_now};
+use std::collections::VecDeque;
+use std::sync::mpsc;
+
+/// Oldest events are dropped once the log holds this many.
+const MAX_EVENTS: usize = 2000;
+
+/// Part of the app an event came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Subsystem {
+    Refresh,
+    Replays,
+    Rules,
+    Watchlist,
+    Downloads,
+    Uploads,
+    /// Webhook calls and push notifications.
+    Webhook,
+    Errors,
+}
+
+impl Subsystem {
+    pub const ALL: [Subsystem; 8] = [
+        Subsystem::Refresh,
+        Subsystem::Replays,
+        Subsystem::Rules,
+        Subsystem::Watchlist,
+        Subsystem::Downloads,
+        Subsystem::Uploads,
+        Subsystem::Webhook,
+        Subsystem::Errors,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Subsystem::Refresh => "Refresh",
+            Subsystem::Replays => "New replays",
+            Subsystem::Rules => "Rules",
+            Subsystem::Watchlist => "Watchlist",
+            Subsystem::Downloads => "Downloads",
+            Subsystem::Uploads => "Uploads",
+            Subsystem::Webhook => "Webhook & push",
+            Subsystem::Errors => "Errors",
+        }
+    }
+}
+
+/// One entry of the activity feed.
+#[derive(Debug, Clone)]
+pub struct Event {
+    /// Unix timestamp (seconds).
+    pub at: u64,
+    pub subsystem: Subsystem,
+    pub message: String,
+}
+
+/// Handle background threads use to post events to the log.
+#[derive(Clone)]
+pub struct EventSender(mpsc::Sender<Event>);
+
+impl EventSender {
+    pub fn send(&self, subsystem: Subsystem, message: impl Into<String>) {
+        let _ = self.0.send(Event { at: unix_now(), subsystem, message: message.into() });
+    }
+}
+
+/// In-memory activity feed. The UI thread pushes directly; other threads go
+/// through an [`EventSender`] and their events are collected by [`EventLog::poll`].
+pub struct EventLog {
+    events: VecDeque<Event>,
+    /// Errors logged so far, including ones already dropped from `events`.
+    error_count: usize,
+    tx: EventSender,
+    rx: mpsc::Receiver<Event>,
+}
+
+impl EventLog {
+    pub fn new() -> Self {
+        let (tx, rx) = mpsc::channel();
+        Self { events: VecDeque::new(), error_count: 0, tx: EventSender(tx), rx }
+    }
+
+    pub fn sender(&self) -> EventSender {
+        self.tx.clone()
+    }
+
+    pub fn push(&mut self, subsystem: Subsystem, message: impl Into<String>) {
+        self.append(Event { at: unix_now(), subsystem, message: message.into() });
+    }
+
+    fn append(&mut self, event: Event) {
+        if event.subsystem == Subsystem::Errors {
+            self.error_count += 1;
+        }
+        self.events.push_back(event);
+        if self.events.len() > MAX_EVENTS {
+            self.events.pop_front();
+        }
+    }
+
+    /// Moves events posted by background threads, and failures passed to
+    /// [`error::report`], into the log. Call once per frame.
+    pub fn poll(&mut self) {
+        while let Ok(event) = self.rx.try_recv() {
+            self.append(event);
+        }
+        for message in error::take_reported() {
+            self.push(Subsystem::Errors, message);
+        }
+    }
+
+    /// Number of errors logged so far; grows by one with every error.
+    pub fn error_count(&self) -> usize {
+        self.error_count
+    }
+
+    /// Events from oldest to newest.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &Event> {
+        self.events.iter()
+    }
+}
@@ -0,0 +1,67 @@
+use crate::Replay;
+use serde::Serialize;
+use std::path::Path;
+
+/// A replay as exported: every field of the list plus what was resolved for it.
+#[derive(Serialize)]
+pub struct Row {
+    #[serde(flatten)]
+    pub replay: Replay,
+    /// Server the replay is listed on.
+    pub server: String,
+    /// Workshop map name; empty if it is not known (yet).
+    pub map: String,
+    /// Nicknames of `users`, or their ids where there is none.
+    pub user_names: Vec<String>,
+}
+
+/// Quotes a CSV field if it needs it.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_owned()
+    }
+}
+
+fn to_csv(rows: &[Row]) -> String {
+    let mut out = String::from(
+        "id,friendlyName,gameMode,created,expires,live,competitive,shack,workshop_id,map,workshop_mods,modcount,secondsSince,users,user_names,server\n",
+    );
+    for row in rows {
+        let r = &row.replay;
+        let fields = [
+            r._id.clone(),
+            r.friendlyName.clone(),
+            r.gameMode.clone(),
+            r.created.clone(),
+            r.expires.clone(),
+            r.live.to_string(),
+            r.competitive.to_string(),
+            r.shack.to_string(),
+            r.workshop_id.clone(),
+            row.map.clone(),
+            r.workshop_mods.clone(),
+            r.modcount.to_string(),
+            r.secondsSince.to_string(),
+            r.users.join(";"),
+            row.user_names.join(";"),
+            row.server.clone(),
+        ];
+        out.push_str(&fields.iter().map(|field| csv_field(field)).collect::<Vec<_>>().join(","));
+        out.push('\n');
+    }
+    out
+}
+
+/// Writes the rows as a JSON array if `path` ends in `.json`, as CSV otherwise (lists
+/// separated by `;`).
+pub fn write(path: &Path, rows: &[Row]) -> Result<(), String> {
+    let json = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+    let text = if json {
+        serde_json::to_string_pretty(rows).map_err(|err| err.to_string())?
+    } else {
+        to_csv(rows)
+    };
+    std::fs::write(path, text).map_err(|err| format!("{}: {}", path.display(), err))
+}
@@ -0,0 +1,473 @@
+//! A tiny expression language over replay fields, e.g. `users.len() >= 10 && competitive`.
+//!
+//! Supports bool/number/string literals, field access, `.len()` and `.contains(..)`,
+//! arithmetic (`+ - * /`), comparisons (`== != < <= > >=`), `!`, `&&`, `||` and parentheses.
+
+use std::fmt;
+
+/// A value produced while evaluating an expression.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Bool(bool),
+    Num(f64),
+    Str(String),
+    List(Vec<String>),
+}
+
+impl Value {
+    /// Truthiness used by `!`, `&&`, `||` and filters.
+    pub fn truthy(&self) -> bool {
+        match self {
+            Value::Bool(b) => *b,
+            Value::Num(n) => *n != 0.0,
+            Value::Str(s) => !s.is_empty(),
+            Value::List(l) => !l.is_empty(),
+        }
+    }
+
+    /// Orders two values of the same kind (used for sorting by a computed column).
+    pub fn compare(&self, other: &Value) -> std::cmp::Ordering {
+        use std::cmp::Ordering;
+        match (self, other) {
+            (Value::Bool(a), Value::Bool(b)) => a.cmp(b),
+            (Value::Num(a), Value::Num(b)) => a.partial_cmp(b).unwrap_or(Ordering::Equal),
+            (Value::Str(a), Value::Str(b)) => a.cmp(b),
+            (Value::List(a), Value::List(b)) => a.len().cmp(&b.len()),
+            _ => Ordering::Equal,
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::Num(n) if n.fract() == 0.0 => write!(f, "{}", *n as i64),
+            Value::Num(n) => write!(f, "{:.2}", n),
+            Value::Str(s) => write!(f, "{}", s),
+            Value::List(l) => write!(f, "{}", l.join(", ")),
+        }
+    }
+}
+
+/// Anything expressions can be evaluated against.
+pub trait Fields {
+    /// Returns the value of a named field, or `None` if there is no such field.
+    fn field(&self, name: &str) -> Option<Value>;
+}
+
+/// Binary operators.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BinOp {
+    Or,
+    And,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+/// A parsed expression.
+#[derive(Debug, Clone)]
+pub enum Expr {
+    Literal(Value),
+    Field(String),
+    Not(Box<Expr>),
+    Neg(Box<Expr>),
+    Binary(BinOp, Box<Expr>, Box<Expr>),
+    Method(Box<Expr>, String, Vec<Expr>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Num(f64),
+    Str(String),
+    Ident(String),
+    Op(&'static str),
+    LParen,
+    RParen,
+    Dot,
+    Comma,
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    const OPS: [&str; 15] = ["&&", "||", "==", "!=", "<=", ">=", "<", ">", "!", "+", "-", "*", "/", "=", "&"];
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit() {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            let text: String = chars[start..i].iter().collect();
+            tokens.push(Token::Num(text.parse().map_err(|_| format!("Invalid number '{}'", text))?));
+        } else if c == '"' || c == '\'' {
+            let start = i + 1;
+            i += 1;
+            while i < chars.len() && chars[i] != c {
+                i += 1;
+            }
+            if i >= chars.len() {
+                return Err("Unterminated string".to_owned());
+            }
+            tokens.push(Token::Str(chars[start..i].iter().collect()));
+            i += 1;
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push(Token::Ident(chars[start..i].iter().collect()));
+        } else if c == '(' {
+            tokens.push(Token::LParen);
+            i += 1;
+        } else if c == ')' {
+            tokens.push(Token::RParen);
+            i += 1;
+        } else if c == '.' {
+            tokens.push(Token::Dot);
+            i += 1;
+        } else if c == ',' {
+            tokens.push(Token::Comma);
+            i += 1;
+        } else {
+            let rest: String = chars[i..].iter().take(2).collect();
+            let op = OPS
+                .iter()
+                .find(|op| rest.starts_with(*op))
+                .ok_or_else(|| format!("Unexpected character '{}'", c))?;
+            if *op == "=" || *op == "&" {
+                return Err(format!("Unknown operator '{}', did you mean '{}{}'?", op, op, op));
+            }
+            tokens.push(Token::Op(op));
+            i += op.len();
+        }
+    }
+    Ok(tokens)
+}
+
+/// Deepest an expression may nest, counting parentheses, prefix operators, and chained
+/// binary operators and method calls. Parsing and evaluation recurse once per level, so
+/// anything deeper is rejected instead of overflowing the stack.
+const MAX_DEPTH: usize = 128;
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    depth: usize,
+}
+
+impl Parser {
+    /// Goes one level deeper, failing past `MAX_DEPTH`.
+    fn enter(&mut self) -> Result<(), String> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(format!("Expression is nested more than {} levels deep", MAX_DEPTH));
+        }
+        Ok(())
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn eat_op(&mut self, ops: &[(&str, BinOp)]) -> Option<BinOp> {
+        if let Some(Token::Op(op)) = self.peek() {
+            if let Some((_, bin)) = ops.iter().find(|(o, _)| o == op) {
+                self.pos += 1;
+                return Some(*bin);
+            }
+        }
+        None
+    }
+
+    fn binary(&mut self, ops: &[(&str, BinOp)], next: fn(&mut Self) -> Result<Expr, String>) -> Result<Expr, String> {
+        let mut left = next(self)?;
+        // Each operator adds a level to the left-leaning tree.
+        let depth = self.depth;
+        while let Some(op) = self.eat_op(ops) {
+            self.enter()?;
+            let right = next(self)?;
+            left = Expr::Binary(op, Box::new(left), Box::new(right));
+        }
+        self.depth = depth;
+        Ok(left)
+    }
+
+    fn or(&mut self) -> Result<Expr, String> {
+        self.binary(&[("||", BinOp::Or)], Self::and)
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        self.binary(&[("&&", BinOp::And)], Self::comparison)
+    }
+
+    fn comparison(&mut self) -> Result<Expr, String> {
+        self.binary(
+            &[("==", BinOp::Eq), ("!=", BinOp::Ne), ("<=", BinOp::Le), (">=", BinOp::Ge), ("<", BinOp::Lt), (">", BinOp::Gt)],
+            Self::sum,
+        )
+    }
+
+    fn sum(&mut self) -> Result<Expr, String> {
+        self.binary(&[("+", BinOp::Add), ("-", BinOp::Sub)], Self::product)
+    }
+
+    fn product(&mut self) -> Result<Expr, String> {
+        self.binary(&[("*", BinOp::Mul), ("/", BinOp::Div)], Self::unary)
+    }
+
+    /// Every nested expression, whether in parentheses, method arguments or after a prefix
+    /// operator, passes through here, so this is where nesting is counted.
+    fn unary(&mut self) -> Result<Expr, String> {
+        self.enter()?;
+        let expr = match self.peek() {
+            Some(Token::Op("!")) => {
+                self.pos += 1;
+                Expr::Not(Box::new(self.unary()?))
+            }
+            Some(Token::Op("-")) => {
+                self.pos += 1;
+                Expr::Neg(Box::new(self.unary()?))
+            }
+            _ => self.postfix()?,
+        };
+        self.depth -= 1;
+        Ok(expr)
+    }
+
+    fn postfix(&mut self) -> Result<Expr, String> {
+        let mut expr = self.primary()?;
+        // Like binary operators, each call in a chain adds a level.
+        let depth = self.depth;
+        while self.peek() == Some(&Token::Dot) {
+            self.enter()?;
+            self.pos += 1;
+            let Some(Token::Ident(method)) = self.next() else {
+                return Err("Expected a method name after '.'".to_owned());
+            };
+            if self.next() != Some(Token::LParen) {
+                return Err(format!("Expected '(' after '{}'", method));
+            }
+            let mut args = Vec::new();
+            if self.peek() != Some(&Token::RParen) {
+                loop {
+                    args.push(self.or()?);
+                    if self.peek() == Some(&Token::Comma) {
+                        self.pos += 1;
+                    } else {
+                        break;
+                    }
+                }
+            }
+            if self.next() != Some(Token::RParen) {
+                return Err(format!("Expected ')' to close '{}('", method));
+            }
+            expr = Expr::Method(Box::new(expr), method, args);
+        }
+        self.depth = depth;
+        Ok(expr)
+    }
+
+    fn primary(&mut self) -> Result<Expr, String> {
+        match self.next() {
+            Some(Token::Num(n)) => Ok(Expr::Literal(Value::Num(n))),
+            Some(Token::Str(s)) => Ok(Expr::Literal(Value::Str(s))),
+            Some(Token::Ident(name)) if name == "true" => Ok(Expr::Literal(Value::Bool(true))),
+            Some(Token::Ident(name)) if name == "false" => Ok(Expr::Literal(Value::Bool(false))),
+            Some(Token::Ident(name)) => Ok(Expr::Field(name)),
+            Some(Token::LParen) => {
+                let expr = self.or()?;
+                if self.next() != Some(Token::RParen) {
+                    return Err("Expected ')'".to_owned());
+                }
+                Ok(expr)
+            }
+            Some(token) => Err(format!("Unexpected {:?}", token)),
+            None => Err("Unexpected end of expression".to_owned()),
+        }
+    }
+}
+
+/// Parses an expression, returning a readable error message on failure.
+pub fn parse(source: &str) -> Result<Expr, String> {
+    let mut parser = Parser { tokens: tokenize(source)?, pos: 0, depth: 0 };
+    let expr = parser.or()?;
+    if parser.pos < parser.tokens.len() {
+        return Err(format!("Unexpected {:?}", parser.tokens[parser.pos]));
+    }
+    Ok(expr)
+}
+
+impl Expr {
+    /// Evaluates the expression against the given fields.
+    pub fn eval(&self, fields: &dyn Fields) -> Result<Value, String> {
+        match self {
+            Expr::Literal(value) => Ok(value.clone()),
+            Expr::Field(name) => fields.field(name).ok_or_else(|| format!("Unknown field '{}'", name)),
+            Expr::Not(inner) => Ok(Value::Bool(!inner.eval(fields)?.truthy())),
+            Expr::Neg(inner) => match inner.eval(fields)? {
+                Value::Num(n) => Ok(Value::Num(-n)),
+                other => Err(format!("Cannot negate {}", other)),
+            },
+            Expr::Binary(BinOp::And, l, r) => Ok(Value::Bool(l.eval(fields)?.truthy() && r.eval(fields)?.truthy())),
+            Expr::Binary(BinOp::Or, l, r) => Ok(Value::Bool(l.eval(fields)?.truthy() || r.eval(fields)?.truthy())),
+            Expr::Binary(op, l, r) => binary(*op, l.eval(fields)?, r.eval(fields)?),
+            Expr::Method(target, name, args) => {
+                let target = target.eval(fields)?;
+                let args = args.iter().map(|a| a.eval(fields)).collect::<Result<Vec<_>, _>>()?;
+                method(target, name, &args)
+            }
+        }
+    }
+}
+
+fn binary(op: BinOp, left: Value, right: Value) -> Result<Value, String> {
+    use std::cmp::Ordering;
+    match op {
+        BinOp::Eq => Ok(Value::Bool(left == right)),
+        BinOp::Ne => Ok(Value::Bool(left != right)),
+        BinOp::Lt | BinOp::Le | BinOp::Gt | BinOp::Ge => {
+            let ordering = match (&left, &right) {
+                (Value::Num(a), Value::Num(b)) => a.partial_cmp(b).unwrap_or(Ordering::Equal),
+                (Value::Str(a), Value::Str(b)) => a.cmp(b),
+                _ => return Err(format!("Cannot compare {} with {}", left, right)),
+            };
+            Ok(Value::Bool(match op {
+                BinOp::Lt => ordering == Ordering::Less,
+                BinOp::Le => ordering != Ordering::Greater,
+                BinOp::Gt => ordering == Ordering::Greater,
+                _ => ordering != Ordering::Less,
+            }))
+        }
+        BinOp::Add => match (left, right) {
+            (Value::Num(a), Value::Num(b)) => Ok(Value::Num(a + b)),
+            (Value::Str(a), b) => Ok(Value::Str(format!("{}{}", a, b))),
+            (a, b) => Err(format!("Cannot add {} and {}", a, b)),
+        },
+        BinOp::Sub | BinOp::Mul | BinOp::Div => match (left, right) {
+            (Value::Num(a), Value::Num(b)) => Ok(Value::Num(match op {
+                BinOp::Sub => a - b,
+                BinOp::Mul => a * b,
+                _ if b == 0.0 => return Err("Division by zero".to_owned()),
+                _ => a / b,
+            })),
+            (a, b) => Err(format!("Arithmetic needs numbers, got {} and {}", a, b)),
+        },
+        BinOp::And | BinOp::Or => unreachable!("handled with short-circuiting in eval"),
+    }
+}
+
+fn method(target: Value, name: &str, args: &[Value]) -> Result<Value, String> {
+    match (name, &target, args) {
+        ("len", Value::List(l), []) => Ok(Value::Num(l.len() as f64)),
+        ("len", Value::Str(s), []) => Ok(Value::Num(s.chars().count() as f64)),
+        ("contains", Value::List(l), [Value::Str(needle)]) => Ok(Value::Bool(l.iter().any(|u| u.contains(needle.as_str())))),
+        ("contains", Value::Str(s), [Value::Str(needle)]) => Ok(Value::Bool(s.contains(needle.as_str()))),
+        ("lower", Value::Str(s), []) => Ok(Value::Str(s.to_lowercase())),
+        _ => Err(format!("Unknown method '{}' for {}", name, target)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    impl Fields for HashMap<&str, Value> {
+        fn field(&self, name: &str) -> Option<Value> {
+            self.get(name).cloned()
+        }
+    }
+
+    fn replay() -> HashMap<&'static str, Value> {
+        HashMap::from([
+            ("users", Value::List(vec!["alice".to_owned(), "bob".to_owned(), "carol".to_owned()])),
+            ("gameMode", Value::Str("SND".to_owned())),
+            ("competitive", Value::Bool(true)),
+            ("secondsSince", Value::Num(90.0)),
+        ])
+    }
+
+    fn eval(source: &str) -> Result<Value, String> {
+        parse(source)?.eval(&replay())
+    }
+
+    #[test]
+    fn evaluates_fields_methods_and_literals() {
+        assert_eq!(eval("users.len() >= 3 && competitive"), Ok(Value::Bool(true)));
+        assert_eq!(eval("users.contains('bo')"), Ok(Value::Bool(true)));
+        assert_eq!(eval("gameMode.lower() == \"snd\""), Ok(Value::Bool(true)));
+        assert_eq!(eval("gameMode + \"-\" + users.len()"), Ok(Value::Str("SND-3".to_owned())));
+        assert_eq!(eval("-secondsSince / 60"), Ok(Value::Num(-1.5)));
+    }
+
+    #[test]
+    fn respects_precedence() {
+        assert_eq!(eval("1 + 2 * 3"), Ok(Value::Num(7.0)));
+        assert_eq!(eval("(1 + 2) * 3"), Ok(Value::Num(9.0)));
+        assert_eq!(eval("10 - 4 - 3"), Ok(Value::Num(3.0)));
+        assert_eq!(eval("true || false && false"), Ok(Value::Bool(true)));
+        assert_eq!(eval("!competitive || 1 < 2"), Ok(Value::Bool(true)));
+        assert!(matches!(parse("1 + 2 * 3"), Ok(Expr::Binary(BinOp::Add, _, _))));
+    }
+
+    #[test]
+    fn short_circuits() {
+        // The right-hand side would fail on the unknown field if it were evaluated.
+        assert_eq!(eval("false && missing"), Ok(Value::Bool(false)));
+        assert_eq!(eval("true || missing"), Ok(Value::Bool(true)));
+        assert_eq!(eval("true && missing"), Err("Unknown field 'missing'".to_owned()));
+    }
+
+    #[test]
+    fn reports_syntax_errors() {
+        assert_eq!(parse("gameMode == 'SND").err(), Some("Unterminated string".to_owned()));
+        assert_eq!(parse("a = 1").err(), Some("Unknown operator '=', did you mean '=='?".to_owned()));
+        assert_eq!(parse("a & b").err(), Some("Unknown operator '&', did you mean '&&'?".to_owned()));
+        assert_eq!(parse("(1 + 2").err(), Some("Expected ')'".to_owned()));
+        assert_eq!(parse("1 +").err(), Some("Unexpected end of expression".to_owned()));
+        assert_eq!(parse("users.len(").err(), Some("Unexpected end of expression".to_owned()));
+        assert_eq!(parse("1 2").err(), Some("Unexpected Num(2.0)".to_owned()));
+        assert_eq!(parse("a # b").err(), Some("Unexpected character '#'".to_owned()));
+    }
+
+    #[test]
+    fn reports_evaluation_errors() {
+        assert_eq!(eval("1 / 0"), Err("Division by zero".to_owned()));
+        assert_eq!(eval("1 / (secondsSince - 90)"), Err("Division by zero".to_owned()));
+        assert_eq!(eval("users < 3"), Err("Cannot compare alice, bob, carol with 3".to_owned()));
+        assert_eq!(eval("-gameMode"), Err("Cannot negate SND".to_owned()));
+        assert_eq!(eval("competitive * 2"), Err("Arithmetic needs numbers, got true and 2".to_owned()));
+        assert_eq!(eval("users.upper()"), Err("Unknown method 'upper' for alice, bob, carol".to_owned()));
+    }
+
+    #[test]
+    fn rejects_deep_nesting() {
+        let deep = format!("{}1{}", "(".repeat(10_000), ")".repeat(10_000));
+        assert_eq!(parse(&deep).err(), Some(format!("Expression is nested more than {} levels deep", MAX_DEPTH)));
+        assert!(parse(&"!".repeat(10_000)).is_err());
+        assert!(parse(&vec!["1"; 10_000].join(" + ")).is_err());
+        assert!(parse(&format!("gameMode{}", ".lower()".repeat(10_000))).is_err());
+
+        let shallow = format!("{}1{}", "(".repeat(MAX_DEPTH - 1), ")".repeat(MAX_DEPTH - 1));
+        assert_eq!(eval(&shallow), Ok(Value::Num(1.0)));
+    }
+}
@@ -0,0 +1,282 @@
+use crate::aliases::Aliases;
+use crate::cli::Cli;
+use crate::deeplink::Link;
+use crate::instance::{self, Instance, Message, Start};
+use crate::library::{Library, LibraryEntry};
+use crate::{paths, profiles};
+use crate::push;
+use crate::webhook::{self, Event};
+use crate::{
+    auto_download_matches, capabilities, download_replay, fetch_list, http, load_settings, parse_timestamp, unix_now, DownloadResult, Replay,
+    Settings,
+};
+use chrono::Local;
+use std::fmt::Display;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Timestamped log lines on the console and, if `--log-file` was given, appended to that file.
+/// Without a console (e.g. run by a service on Windows) they go to `<profile>.headless.log`
+/// next to the config file unless `--log-file` says otherwise.
+struct Log {
+    file: Option<File>,
+}
+
+impl Log {
+    fn open(config_name: Option<&str>, path: Option<&Path>, console: bool) -> Self {
+        let default = if console { None } else { paths::config_file(config_name).ok().map(|file| file.with_extension("headless.log")) };
+        let file = path.or(default.as_deref()).and_then(|path| match OpenOptions::new().create(true).append(true).open(path) {
+            Ok(file) => Some(file),
+            Err(err) => {
+                eprintln!("Error opening log file {}: {}", path.display(), err);
+                None
+            }
+        });
+        Self { file }
+    }
+
+    fn line(&mut self, message: impl Display) {
+        let line = format!("[{}] {}", Local::now().format("%Y-%m-%d %H:%M:%S"), message);
+        println!("{}", line);
+        if let Some(file) = &mut self.file {
+            let _ = writeln!(file, "{}", line);
+        }
+    }
+}
+
+/// Adds a finished download to the library of profile `config_name`.
+fn record(config_name: Option<&str>, replay_id: String, replay: Option<&Replay>, server: &str, bytes: u64, seconds: f64) -> Result<(), String> {
+    let mut library = Library::load(config_name);
+    library.transferred_bytes += bytes;
+    library.entries.push(LibraryEntry {
+        replay_id,
+        friendly_name: replay.map(|r| r.friendlyName.clone()).unwrap_or_default(),
+        game_mode: replay.map(|r| r.gameMode.clone()).unwrap_or_default(),
+        users: replay.map(|r| r.users.clone()).unwrap_or_default(),
+        downloaded_at: unix_now(),
+        bytes,
+        rating: 0,
+        server: server.to_owned(),
+        seconds,
+        missing_on_server: false,
+    });
+    library.save(config_name)
+}
+
+/// Calls the webhook for `event` if the settings set one up for it, and waits for the answer.
+fn send_webhook(settings: &Settings, event: Event, fields: webhook::Fields) -> Result<(), String> {
+    match settings.webhook(event) {
+        Some(config) => webhook::send_blocking(&config, event, &fields),
+        None => Ok(()),
+    }
+}
+
+/// Handles `--headless`. With `--download` the replay is downloaded, with `--page` that page
+/// of the list is printed; otherwise the refresh and auto-download loop runs until the
+/// process is stopped. `console` tells whether printed lines are seen. Returns the process
+/// exit code.
+pub fn run(cli: &Cli, console: bool) -> i32 {
+    let config_name = cli.profile.as_deref();
+    let mut settings = load_settings(config_name);
+    cli.apply(&mut settings);
+    http::configure(settings.http_config());
+    let server_addr = settings.active_server().to_owned();
+    if let Some(replay_id) = &cli.download {
+        // A running instance of the profile downloads it, so the library has a single writer.
+        let link = Link { replay_id: replay_id.clone(), server: Some(server_addr.clone()) };
+        let _instance = match instance::start(config_name, &Message::Download(link), true) {
+            Start::First(instance) => Some(instance),
+            Start::Forwarded { .. } => {
+                println!("Handed the download to the running instance of this profile");
+                return 0;
+            }
+            Start::Unavailable(err) => {
+                eprintln!("Error checking for a running instance: {}", err);
+                None
+            }
+        };
+        return match download_replay(&server_addr, replay_id.clone(), false) {
+            DownloadResult::Success { message, replay_id, bytes, seconds, .. } => {
+                println!("{}", message);
+                let fields = webhook::Fields::new(&replay_id, None, &server_addr, &message);
+                if let Err(err) = send_webhook(&settings, Event::DownloadComplete, fields) {
+                    eprintln!("Error sending webhook: {}", err);
+                }
+                if let Err(err) = record(config_name, replay_id, None, &server_addr, bytes, seconds) {
+                    eprintln!("Error saving library: {}", err);
+                }
+                0
+            }
+            DownloadResult::Failure { message, replay_id, .. } => {
+                eprintln!("{}", message);
+                let fields = webhook::Fields::new(&replay_id, None, &server_addr, &message);
+                if let Err(err) = send_webhook(&settings, Event::DownloadFailed, fields) {
+                    eprintln!("Error sending webhook: {}", err);
+                }
+                1
+            }
+        };
+    }
+    if let Some(page) = cli.page {
+        let paging = settings.paging(&capabilities::detect(&server_addr));
+        let list_url = paging.url(&server_addr, (page.get() - 1) * paging.size);
+        return match fetch_list(&http::client(&server_addr), &list_url) {
+            Ok(list) => {
+                for replay in list.replays {
+                    println!("{}\t{}\t{}\t{}", replay._id, replay.created, replay.gameMode, replay.friendlyName);
+                }
+                0
+            }
+            Err(err) => {
+                eprintln!("{}", err);
+                1
+            }
+        };
+    }
+    let mut log = Log::open(config_name, cli.log_file.as_deref(), console);
+    let instance = match instance::start(config_name, &Message::Ping, true) {
+        Start::First(instance) => Some(instance),
+        Start::Forwarded { headless } => {
+            let running = if headless { "another --headless instance" } else { "the LocalPavTV window" };
+            log.line(format!("This profile is in use by {}; not starting, so replays are not downloaded twice", running));
+            return 1;
+        }
+        Start::Unavailable(err) => {
+            log.line(format!("Error checking for a running instance: {}", err));
+            None
+        }
+    };
+    daemon(cli, instance.as_ref(), &mut log)
+}
+
+/// Pushes an archived replay to the phone, if a push service is set up and its filter matches.
+fn send_push(settings: &Settings, replay: &Replay) -> Result<(), String> {
+    match settings.push() {
+        Some(config) if push::matches(&settings.push_filter, replay, &Aliases::load()) => {
+            push::send_blocking(&config, "Replay archived", &format!("{} ({}) was downloaded", replay.friendlyName, replay.gameMode))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Downloads a replay in the daemon, calling the webhook and push service and adding it to
+/// the library. `auto` is set for downloads the auto-download filter started.
+fn download(config_name: Option<&str>, settings: &Settings, server_addr: &str, replay_id: String, replay: Option<&Replay>, auto: bool, log: &mut Log) {
+    match download_replay(server_addr, replay_id, auto) {
+        DownloadResult::Success { message, replay_id, bytes, seconds, .. } => {
+            let fields = webhook::Fields::new(&replay_id, replay, server_addr, &message);
+            if let Err(err) = send_webhook(settings, Event::DownloadComplete, fields) {
+                log.line(format!("Error sending webhook: {}", err));
+            }
+            if let Some(replay) = replay {
+                if let Err(err) = send_push(settings, replay) {
+                    log.line(format!("Error sending push notification: {}", err));
+                }
+            }
+            log.line(message);
+            if let Err(err) = record(config_name, replay_id, replay, server_addr, bytes, seconds) {
+                log.line(format!("Error saving library: {}", err));
+            }
+        }
+        DownloadResult::Failure { message, replay_id, .. } => {
+            let fields = webhook::Fields::new(&replay_id, replay, server_addr, &message);
+            if let Err(err) = send_webhook(settings, Event::DownloadFailed, fields) {
+                log.line(format!("Error sending webhook: {}", err));
+            }
+            log.line(message);
+        }
+    }
+}
+
+/// Downloads the replays later launches hand over until `deadline`. Links to servers no
+/// profile uses are ignored, as nobody is there to confirm them.
+fn handle_messages(instance: &Instance, deadline: Instant, config_name: Option<&str>, settings: &Settings, log: &mut Log) {
+    while let Some(message) = instance.wait_until(deadline) {
+        let Message::Download(link) = message else {
+            continue;
+        };
+        let server_addr = match link.server {
+            None => settings.active_server().to_owned(),
+            Some(server) => {
+                let normalized = profiles::normalize_url(&server);
+                let known = [settings.server_addr.as_str(), settings.backup_server_addr.as_str()]
+                    .iter()
+                    .any(|addr| profiles::normalize_url(addr) == normalized)
+                    || profiles::servers().contains(&normalized);
+                if !known {
+                    log.line(format!("Ignored a link to {} on {}, which no profile uses", link.replay_id, server));
+                    continue;
+                }
+                server
+            }
+        };
+        log.line(format!("Downloading {} from {}, as a later launch asked", link.replay_id, server_addr));
+        download(config_name, settings, &server_addr, link.replay_id, None, false, log);
+    }
+}
+
+/// Polls the newest page every refresh interval and downloads the replays the auto-download
+/// filter matches, calling the webhook for new replays and downloads. Settings are re-read
+/// each round, so edits apply without a restart. Between rounds, downloads handed over by
+/// later launches through `instance` are done.
+fn daemon(cli: &Cli, instance: Option<&Instance>, log: &mut Log) -> i32 {
+    let config_name = cli.profile.as_deref();
+    log.line(format!("Running headless (LocalPavTV_GUI {}); stop with Ctrl+C", env!("CARGO_PKG_VERSION")));
+    let mut capabilities_for: Option<(String, capabilities::Capabilities)> = None;
+    // Replays created after the newest one seen are new; the first round only sets the mark.
+    let mut newest_seen = None;
+    loop {
+        let mut settings = load_settings(config_name);
+        cli.apply(&mut settings);
+        http::configure(settings.http_config());
+        let server_addr = settings.active_server().to_owned();
+        let capabilities = match &capabilities_for {
+            Some((server, capabilities)) if *server == server_addr => capabilities.clone(),
+            _ => {
+                let capabilities = capabilities::detect(&server_addr);
+                capabilities_for = Some((server_addr.clone(), capabilities.clone()));
+                capabilities
+            }
+        };
+        let filter = settings.auto_download_filter.clone();
+        if filter.is_empty() {
+            log.line("No auto-download filter is set, so nothing is downloaded");
+        }
+        let list_url = settings.paging(&capabilities).url(&server_addr, 0);
+        match fetch_list(&http::client(&server_addr), &list_url) {
+            Ok(list) => {
+                let downloaded = Library::load(config_name).downloaded_on(&server_addr);
+                let matched: Vec<&Replay> = list
+                    .replays
+                    .iter()
+                    .filter(|r| !filter.is_empty() && !downloaded.contains(&r._id) && auto_download_matches(r, &filter))
+                    .collect();
+                log.line(format!("Refreshed {} ({} replays, {} to download)", list_url, list.replays.len(), matched.len()));
+                if let Some(seen) = newest_seen {
+                    for replay in list.replays.iter().filter(|r| parse_timestamp(&r.created).is_some_and(|created| created > seen)) {
+                        let message = format!("New replay: {} ({})", replay.friendlyName, replay.gameMode);
+                        let fields = webhook::Fields::new(&replay._id, Some(replay), &server_addr, &message);
+                        log.line(message);
+                        if let Err(err) = send_webhook(&settings, Event::NewReplay, fields) {
+                            log.line(format!("Error sending webhook: {}", err));
+                        }
+                    }
+                }
+                newest_seen = newest_seen.max(list.replays.iter().filter_map(|r| parse_timestamp(&r.created)).max());
+                for replay in matched {
+                    log.line(format!("Auto-download filter \"{}\" matched {}", filter, replay.friendlyName));
+                    download(config_name, &settings, &server_addr, replay._id.clone(), Some(replay), true, log);
+                }
+            }
+            Err(err) => log.line(err),
+        }
+        let interval = Duration::from_secs(settings.refresh_interval.max(10));
+        match instance {
+            Some(instance) => handle_messages(instance, Instant::now() + interval, config_name, &settings, log),
+            None => thread::sleep(interval),
+        }
+    }
+}
@@ -0,0 +1,70 @@
+use eframe::egui;
+use global_hotkey::hotkey::HotKey;
+use global_hotkey::{GlobalHotKeyEvent, GlobalHotKeyManager, HotKeyState};
+use std::sync::mpsc;
+
+/// A system-wide hotkey that works while another window, e.g. the game, has focus.
+/// Only X11 is supported on Linux; Wayland compositors don't allow grabbing keys.
+pub struct Hotkey {
+    manager: GlobalHotKeyManager,
+    registered: Option<HotKey>,
+    /// Text the registered hotkey was parsed from, so unchanged settings are not re-registered.
+    text: String,
+    error: Option<String>,
+    pressed: mpsc::Receiver<u32>,
+}
+
+/// Sets up global hotkeys. Presses wake the UI through `ctx`, so they are handled even
+/// while the window is hidden. Must be called on the thread running the event loop.
+pub fn create(ctx: &egui::Context) -> Result<Hotkey, String> {
+    let manager = GlobalHotKeyManager::new().map_err(|err| err.to_string())?;
+    let (tx, pressed) = mpsc::channel();
+    let ctx = ctx.clone();
+    GlobalHotKeyEvent::set_event_handler(Some(move |event: GlobalHotKeyEvent| {
+        if event.state == HotKeyState::Pressed {
+            let _ = tx.send(event.id);
+            ctx.request_repaint();
+        }
+    }));
+    Ok(Hotkey { manager, registered: None, text: String::new(), error: None, pressed })
+}
+
+impl Hotkey {
+    /// Replaces the hotkey with the one described by `text`, e.g. `ctrl+alt+KeyS`;
+    /// empty removes it. Does nothing if `text` is what was set already.
+    pub fn set(&mut self, text: &str) {
+        let text = text.trim();
+        if text == self.text {
+            return;
+        }
+        self.text = text.to_owned();
+        if let Some(old) = self.registered.take() {
+            let _ = self.manager.unregister(old);
+        }
+        self.error = self.register(text).err();
+    }
+
+    fn register(&mut self, text: &str) -> Result<(), String> {
+        if text.is_empty() {
+            return Ok(());
+        }
+        let hotkey: HotKey = text.parse().map_err(|err| format!("Invalid hotkey `{}`: {}", text, err))?;
+        self.manager.register(hotkey).map_err(|err| format!("Could not register `{}`: {}", text, err))?;
+        self.registered = Some(hotkey);
+        Ok(())
+    }
+
+    /// Why the hotkey last set could not be used.
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    /// Whether the hotkey was pressed since the last call.
+    pub fn poll(&self) -> bool {
+        let Some(registered) = self.registered else {
+            return false;
+        };
+        // Drain every press, not just up to the first match.
+        self.pressed.try_iter().filter(|id| *id == registered.id()).count() > 0
+    }
+}
@@ -0,0 +1,196 @@
+use crate::{profiles, ratelimit};
+use percent_encoding::percent_decode_str;
+use reqwest::blocking::{Client, ClientBuilder};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::{Certificate, Identity, NoProxy, Proxy, Url};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+/// How requests to the replay server are made, taken from the settings. Requests to
+/// third parties (avatars, workshop APIs) do not use it.
+#[derive(Clone, Default, PartialEq)]
+pub struct ServerConfig {
+    /// Extra headers sent with every request, e.g. reverse-proxy auth tokens.
+    pub headers: Vec<(String, String)>,
+    pub proxy: Option<ProxyConfig>,
+    /// Certificate trust per server, keyed by normalized address (see `profiles::normalize_url`).
+    pub tls: BTreeMap<String, TlsConfig>,
+    pub client_identity: Option<IdentityConfig>,
+    pub timeouts: Timeouts,
+    /// Most requests per second sent to the replay server; 0 = unlimited.
+    pub requests_per_second: f64,
+}
+
+/// How far one server's certificate is trusted.
+#[derive(Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TlsConfig {
+    /// PEM or DER file of an extra certificate authority to trust (empty = none).
+    pub ca_certificate: String,
+    /// Skip certificate validation altogether.
+    pub accept_invalid_certs: bool,
+}
+
+/// Request timeouts in seconds; 0 waits indefinitely.
+#[derive(Clone, Copy, PartialEq)]
+pub struct Timeouts {
+    /// Establishing the connection, for every request.
+    pub connect: u64,
+    /// Whole `/list` and `/check` requests.
+    pub list: u64,
+    /// How long a download may go without the server sending anything.
+    pub download_idle: u64,
+}
+
+impl Default for Timeouts {
+    fn default() -> Self {
+        Self { connect: 10, list: 30, download_idle: 300 }
+    }
+}
+
+/// `None` for a disabled (zero) timeout.
+fn seconds(secs: u64) -> Option<Duration> {
+    (secs > 0).then(|| Duration::from_secs(secs))
+}
+
+/// Client certificate presented to servers that require mutual TLS.
+#[derive(Clone, Default, PartialEq)]
+pub struct IdentityConfig {
+    /// PKCS#12 bundle (`.p12`/`.pfx`) or PEM certificate chain.
+    pub certificate: String,
+    /// PEM private key; empty when `certificate` is a PKCS#12 bundle or contains the key.
+    pub key: String,
+    /// Password of a PKCS#12 bundle.
+    pub password: String,
+}
+
+impl IdentityConfig {
+    /// Reads the certificate and key files into a reqwest identity, or says why it is unusable.
+    pub fn load(&self) -> Result<Identity, String> {
+        let read = |path: &str| std::fs::read(path.trim()).map_err(|err| format!("{}: {}", path.trim(), err));
+        let certificate = read(&self.certificate)?;
+        if !certificate.starts_with(b"-----") {
+            return Identity::from_pkcs12_der(&certificate, &self.password)
+                .map_err(|err| format!("{} is not a usable PKCS#12 bundle: {}", self.certificate.trim(), err));
+        }
+        let key = if self.key.trim().is_empty() { certificate.clone() } else { read(&self.key)? };
+        Identity::from_pkcs8_pem(&certificate, &key).map_err(|err| format!("Invalid client certificate or key: {}", err))
+    }
+}
+
+/// HTTP or SOCKS5 proxy the replay server is reached through.
+#[derive(Clone, Default, PartialEq)]
+pub struct ProxyConfig {
+    /// `http://`, `https://`, `socks5://` or `socks5h://` URL.
+    pub url: String,
+    /// Empty for proxies without authentication.
+    pub username: String,
+    pub password: String,
+    /// Connect to servers on this machine directly.
+    pub bypass_local: bool,
+}
+
+impl ProxyConfig {
+    /// The reqwest proxy for this configuration, or why it is unusable.
+    pub fn build(&self) -> Result<Proxy, String> {
+        let mut proxy = Proxy::all(self.url.trim()).map_err(|err| format!("Invalid proxy URL: {}", err))?;
+        if !self.username.is_empty() {
+            proxy = proxy.basic_auth(&self.username, &self.password);
+        }
+        if self.bypass_local {
+            proxy = proxy.no_proxy(NoProxy::from_string("localhost,127.0.0.1,::1"));
+        }
+        Ok(proxy)
+    }
+}
+
+/// Splits credentials off a proxy URL: `(url without them, user name, password)`, or `None`
+/// if it has none (or is not a URL).
+pub fn split_proxy_credentials(url: &str) -> Option<(String, String, String)> {
+    let mut parsed = Url::parse(url.trim()).ok()?;
+    if parsed.username().is_empty() && parsed.password().is_none() {
+        return None;
+    }
+    let decode = |part: &str| percent_decode_str(part).decode_utf8_lossy().into_owned();
+    let username = decode(parsed.username());
+    let password = parsed.password().map(decode).unwrap_or_default();
+    parsed.set_username("").ok()?;
+    parsed.set_password(None).ok()?;
+    Some((parsed.to_string(), username, password))
+}
+
+fn current() -> &'static Mutex<ServerConfig> {
+    static CONFIG: OnceLock<Mutex<ServerConfig>> = OnceLock::new();
+    CONFIG.get_or_init(Default::default)
+}
+
+/// Replaces the configuration used by clients built from now on.
+pub fn configure(config: ServerConfig) {
+    ratelimit::SERVER.set_rate(config.requests_per_second);
+    *current().lock().unwrap() = config;
+}
+
+/// Checks one configured header.
+pub fn parse_header(name: &str, value: &str) -> Result<(HeaderName, HeaderValue), String> {
+    let header_name = HeaderName::from_bytes(name.trim().as_bytes()).map_err(|_| format!("`{}` is not a valid header name", name))?;
+    let header_value = HeaderValue::from_str(value.trim()).map_err(|_| format!("The value of `{}` contains invalid characters", name))?;
+    Ok((header_name, header_value))
+}
+
+/// The configured extra headers. Invalid ones are skipped; Settings points them out.
+pub fn extra_headers() -> HeaderMap {
+    let config = current().lock().unwrap().clone();
+    config
+        .headers
+        .iter()
+        .filter(|(name, _)| !name.trim().is_empty())
+        .filter_map(|(name, value)| parse_header(name, value).ok())
+        .collect()
+}
+
+/// Reads a certificate authority from a PEM or DER file.
+pub fn load_certificate(path: &str) -> Result<Certificate, String> {
+    let bytes = std::fs::read(path).map_err(|err| format!("{}: {}", path, err))?;
+    Certificate::from_pem(&bytes)
+        .or_else(|_| Certificate::from_der(&bytes))
+        .map_err(|err| format!("{} is not a PEM or DER certificate: {}", path, err))
+}
+
+/// Client builder for requests to the replay server at `server_addr`; the certificate
+/// trust configured for it applies to no other server. A proxy or certificate that fails
+/// to load is left out (Settings shows why), so requests fail or succeed visibly.
+pub fn builder(server_addr: &str) -> ClientBuilder {
+    let config = current().lock().unwrap().clone();
+    let tls = config.tls.get(&profiles::normalize_url(server_addr)).cloned().unwrap_or_default();
+    let mut builder = Client::builder().default_headers(extra_headers());
+    if let Some(Ok(proxy)) = config.proxy.map(|proxy| proxy.build()) {
+        builder = builder.proxy(proxy);
+    }
+    if !tls.ca_certificate.trim().is_empty() {
+        if let Ok(certificate) = load_certificate(tls.ca_certificate.trim()) {
+            builder = builder.add_root_certificate(certificate);
+        }
+    }
+    if let Some(Ok(identity)) = config.client_identity.map(|identity| identity.load()) {
+        builder = builder.identity(identity);
+    }
+    builder
+        .danger_accept_invalid_certs(tls.accept_invalid_certs)
+        .connect_timeout(seconds(config.timeouts.connect))
+}
+
+/// Client for list and check requests to the replay server at `server_addr`.
+pub fn client(server_addr: &str) -> Client {
+    let timeout = current().lock().unwrap().timeouts.list;
+    builder(server_addr).timeout(seconds(timeout)).build().expect("Failed to build client")
+}
+
+/// Client for replay downloads from `server_addr`. Blocking reqwest applies the timeout to
+/// waiting for the response and to each read of the body, so read the body in chunks to
+/// make it an idle timeout.
+pub fn download_client(server_addr: &str) -> Client {
+    let timeout = current().lock().unwrap().timeouts.download_idle;
+    builder(server_addr).timeout(seconds(timeout)).build().expect("Failed to build client")
+}
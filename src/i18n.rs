@@ -0,0 +1,96 @@
+//! Translations of the interface. The English text is the key: `locales/<code>.json` maps it
+//! to the translation, and anything a language leaves out is shown in English.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::OnceLock;
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Language {
+    /// Picks one of the others from the operating system locale.
+    System,
+    English,
+    German,
+}
+
+impl Language {
+    /// Languages offered in Settings, each named in itself.
+    pub const CHOICES: [(Language, &'static str); 2] = [(Language::English, "English"), (Language::German, "Deutsch")];
+
+    fn resolve(self) -> Language {
+        match self {
+            Language::System => {
+                let locale = sys_locale::get_locale().unwrap_or_default().to_lowercase();
+                if locale.starts_with("de") {
+                    Language::German
+                } else {
+                    Language::English
+                }
+            }
+            language => language,
+        }
+    }
+
+    /// Translations of a language; `None` for English.
+    fn table(self) -> Option<&'static HashMap<String, String>> {
+        static GERMAN: OnceLock<HashMap<String, String>> = OnceLock::new();
+        match self {
+            Language::German => Some(GERMAN.get_or_init(|| serde_json::from_str(include_str!("../locales/de.json")).expect("locales/de.json is invalid"))),
+            Language::English | Language::System => None,
+        }
+    }
+}
+
+/// Language in use, as its index in `[English, German]`.
+static CURRENT: AtomicU8 = AtomicU8::new(0);
+
+/// Switches the interface language; takes effect on the next frame.
+pub fn set(language: Language) {
+    let index = match language.resolve() {
+        Language::German => 1,
+        _ => 0,
+    };
+    CURRENT.store(index, Ordering::Relaxed);
+}
+
+fn current() -> Language {
+    match CURRENT.load(Ordering::Relaxed) {
+        1 => Language::German,
+        _ => Language::English,
+    }
+}
+
+/// `text` in the current language.
+pub fn tr(text: &'static str) -> &'static str {
+    current().table().and_then(|table| table.get(text)).map_or(text, String::as_str)
+}
+
+/// `template` in the current language with its placeholders filled in: `{}` takes the next
+/// argument, `{0}`, `{1}`, … a given one, so translations can reorder them.
+pub fn trf(template: &'static str, args: &[&dyn Display]) -> String {
+    let mut out = String::new();
+    let mut rest = tr(template);
+    let mut next = 0;
+    while let Some(start) = rest.find('{') {
+        let Some(len) = rest[start..].find('}') else {
+            break;
+        };
+        out.push_str(&rest[..start]);
+        let inner = &rest[start + 1..start + len];
+        let index = if inner.is_empty() {
+            next += 1;
+            Some(next - 1)
+        } else {
+            inner.parse::<usize>().ok()
+        };
+        match index.and_then(|index| args.get(index)) {
+            Some(arg) => out.push_str(&arg.to_string()),
+            None => out.push_str(&rest[start..=start + len]),
+        }
+        rest = &rest[start + len + 1..];
+    }
+    out.push_str(rest);
+    out
+}
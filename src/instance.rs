@@ -0,0 +1,174 @@
+use crate::deeplink::Link;
+use crate::paths;
+use eframe::egui;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Sent by a later launch to the instance that is already running.
+pub enum Message {
+    /// Bring the window to the front.
+    Show,
+    /// Bring the window to the front and download a replay from a `pavlovtv://` link.
+    Download(Link),
+    /// Only asks whether the profile is in use, from a `--headless` launch.
+    Ping,
+}
+
+impl Message {
+    /// One line of tab-separated fields.
+    fn encode(&self) -> String {
+        match self {
+            Message::Show => "show".to_owned(),
+            Message::Download(link) => format!("download\t{}\t{}", link.replay_id, link.server.as_deref().unwrap_or_default()),
+            Message::Ping => "ping".to_owned(),
+        }
+    }
+
+    fn decode(line: &str) -> Option<Message> {
+        let mut fields = line.trim_end_matches(['\r', '\n']).split('\t');
+        match fields.next()? {
+            "show" => Some(Message::Show),
+            "download" => {
+                let replay_id = fields.next().filter(|id| !id.is_empty())?.to_owned();
+                let server = fields.next().filter(|server| !server.is_empty()).map(str::to_owned);
+                Some(Message::Download(Link { replay_id, server }))
+            }
+            "ping" => Some(Message::Ping),
+            _ => None,
+        }
+    }
+}
+
+/// Reply that tells a later launch it reached this app and not some other program that
+/// happens to use the port now.
+const ACKNOWLEDGED: &str = "ok";
+
+/// Reply of an instance running with `--headless`, which has no window to bring up.
+const ACKNOWLEDGED_HEADLESS: &str = "ok headless";
+
+/// How long a later launch waits for the running instance to answer.
+const TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Outcome of starting up.
+pub enum Start {
+    /// This is the only instance of the profile.
+    First(Instance),
+    /// Another instance of the profile runs and got the message. `headless` tells whether it
+    /// runs with `--headless`.
+    Forwarded { headless: bool },
+    /// Could not tell; the app runs without the lock.
+    Unavailable(String),
+}
+
+/// Lock held by the running instance of a profile: a loopback listener whose port is written
+/// to `<profile>.instance` next to the config file.
+pub struct Instance {
+    messages: mpsc::Receiver<Message>,
+    ctx: Arc<Mutex<Option<egui::Context>>>,
+    lock_file: PathBuf,
+    port: u16,
+}
+
+/// Sends `message` to the instance listening on `port`. Returns whether that instance is
+/// headless if it acknowledged the message.
+fn forward(port: u16, message: &Message) -> Option<bool> {
+    let send = || -> std::io::Result<Option<bool>> {
+        let mut stream = TcpStream::connect_timeout(&(Ipv4Addr::LOCALHOST, port).into(), TIMEOUT)?;
+        stream.set_read_timeout(Some(TIMEOUT))?;
+        writeln!(stream, "{}", message.encode())?;
+        let mut reply = String::new();
+        BufReader::new(stream).read_line(&mut reply)?;
+        Ok(match reply.trim_end() {
+            ACKNOWLEDGED => Some(false),
+            ACKNOWLEDGED_HEADLESS => Some(true),
+            _ => None,
+        })
+    };
+    send().ok().flatten()
+}
+
+/// Reads one message from a later launch and acknowledges it with `reply`.
+fn receive(stream: TcpStream, reply: &str) -> Option<Message> {
+    stream.set_read_timeout(Some(TIMEOUT)).ok()?;
+    let mut line = String::new();
+    BufReader::new(&stream).read_line(&mut line).ok()?;
+    let message = Message::decode(&line)?;
+    writeln!(&stream, "{}", reply).ok()?;
+    Some(message)
+}
+
+/// Becomes the running instance of profile `config_name`, or hands `message` to the instance
+/// that already is. A lock file left by a crashed instance is taken over. `headless` is set
+/// for `--headless` launches, so the GUI and a daemon never run on the same profile.
+pub fn start(config_name: Option<&str>, message: &Message, headless: bool) -> Start {
+    let lock_file = match paths::config_file(config_name) {
+        Ok(path) => path.with_extension("instance"),
+        Err(err) => return Start::Unavailable(err),
+    };
+    let running = std::fs::read_to_string(&lock_file).ok().and_then(|port| port.trim().parse().ok());
+    if let Some(headless) = running.and_then(|port| forward(port, message)) {
+        return Start::Forwarded { headless };
+    }
+
+    let listener = match TcpListener::bind((Ipv4Addr::LOCALHOST, 0)) {
+        Ok(listener) => listener,
+        Err(err) => return Start::Unavailable(err.to_string()),
+    };
+    let port = match listener.local_addr() {
+        Ok(addr) => addr.port(),
+        Err(err) => return Start::Unavailable(err.to_string()),
+    };
+    let written = lock_file.parent().map_or(Ok(()), std::fs::create_dir_all).and_then(|_| std::fs::write(&lock_file, port.to_string()));
+    if let Err(err) = written {
+        return Start::Unavailable(format!("{}: {}", lock_file.display(), err));
+    }
+
+    let (tx, messages) = mpsc::channel();
+    let ctx: Arc<Mutex<Option<egui::Context>>> = Arc::default();
+    let repaint = ctx.clone();
+    let reply = if headless { ACKNOWLEDGED_HEADLESS } else { ACKNOWLEDGED };
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let Some(message) = receive(stream, reply) else {
+                continue;
+            };
+            if tx.send(message).is_err() {
+                break;
+            }
+            if let Some(ctx) = &*repaint.lock().unwrap() {
+                ctx.request_repaint();
+            }
+        }
+    });
+    Start::First(Instance { messages, ctx, lock_file, port })
+}
+
+impl Instance {
+    /// Lets messages wake the UI, so they are handled even while the window is hidden.
+    pub fn set_context(&self, ctx: &egui::Context) {
+        *self.ctx.lock().unwrap() = Some(ctx.clone());
+    }
+
+    /// Next message from a later launch, if any.
+    pub fn poll(&self) -> Option<Message> {
+        self.messages.try_recv().ok()
+    }
+
+    /// Next message from a later launch, waiting for one until `deadline`.
+    pub fn wait_until(&self, deadline: Instant) -> Option<Message> {
+        self.messages.recv_timeout(deadline.saturating_duration_since(Instant::now())).ok()
+    }
+}
+
+impl Drop for Instance {
+    /// Removes the lock file, unless another instance has taken it over since.
+    fn drop(&mut self) {
+        if std::fs::read_to_string(&self.lock_file).is_ok_and(|port| port.trim() == self.port.to_string()) {
+            let _ = std::fs::remove_file(&self.lock_file);
+        }
+    }
+}
@@ -0,0 +1,264 @@
+use crate::{error, paths, profiles};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::thread;
+
+/// One replay that was successfully downloaded to the server.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LibraryEntry {
+    pub replay_id: String,
+    pub friendly_name: String,
+    pub game_mode: String,
+    pub users: Vec<String>,
+    /// Unix timestamp (seconds) of when the download finished.
+    pub downloaded_at: u64,
+    /// Size of the `/download/{id}` response. The server does not report the size of the
+    /// stored replay, so this is traffic rather than archive size.
+    pub bytes: u64,
+    /// User rating from 1 to 5 stars; 0 means unrated.
+    #[serde(default)]
+    pub rating: u8,
+    /// Server the replay was downloaded from (empty for entries recorded before this was tracked).
+    #[serde(default)]
+    pub server: String,
+    /// How long the download took (0 for entries recorded before this was tracked).
+    #[serde(default)]
+    pub seconds: f64,
+    /// `/check` still said the replay was missing after the download reported success.
+    #[serde(default)]
+    pub missing_on_server: bool,
+}
+
+/// Freeform note and short tags the user attached to a replay.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReplayNote {
+    pub text: String,
+    pub tags: Vec<String>,
+}
+
+impl ReplayNote {
+    pub fn is_empty(&self) -> bool {
+        self.text.trim().is_empty() && self.tags.is_empty()
+    }
+}
+
+/// Local record of downloaded replays and user annotations, stored as JSON next to the confy file.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Library {
+    pub entries: Vec<LibraryEntry>,
+    /// Notes on replay sessions, keyed by the id of the session's oldest replay.
+    pub session_notes: HashMap<String, String>,
+    /// Replay ids the user chose to hide from the list.
+    pub hidden: HashSet<String>,
+    /// Pinned replays, kept as snapshots so they survive the server expiring them.
+    pub favorites: Vec<crate::Replay>,
+    /// Notes and tags on individual replays, keyed by replay id.
+    pub notes: HashMap<String, ReplayNote>,
+    /// Replays that already raised a watchlist alert.
+    pub watch_alerted: HashSet<String>,
+    /// Bytes received by all downloads ever, including failed ones.
+    pub transferred_bytes: u64,
+}
+
+impl Library {
+    /// Location of the library file for the given confy profile.
+    pub fn path(config_name: Option<&str>) -> Option<PathBuf> {
+        let config_path = paths::config_file(config_name).ok()?;
+        let profile = config_name.unwrap_or("default-config");
+        Some(config_path.parent()?.join(format!("{}.library.json", profile)))
+    }
+
+    /// Loads the library, returning an empty one if the file is missing or unreadable.
+    pub fn load(config_name: Option<&str>) -> Self {
+        Self::try_load(config_name).unwrap_or_else(|err| {
+            error::report("Error loading library", err);
+            Self::default()
+        })
+    }
+
+    /// Loads the library, returning an empty one if the file is missing and an error if it
+    /// cannot be read or parsed.
+    pub fn try_load(config_name: Option<&str>) -> Result<Self, String> {
+        let path = Self::path(config_name).ok_or("Could not determine library path")?;
+        match fs::read_to_string(&path) {
+            Ok(text) => serde_json::from_str(&text).map_err(|err| format!("{}: {}", path.display(), err)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(format!("{}: {}", path.display(), err)),
+        }
+    }
+
+    /// Writes the library to disk. It is written to a temporary file that then replaces the
+    /// old one, so a crash halfway leaves the previous library intact.
+    pub fn save(&self, config_name: Option<&str>) -> Result<(), String> {
+        let path = Self::path(config_name).ok_or("Could not determine library path")?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|err| err.to_string())?;
+        }
+        let text = serde_json::to_string_pretty(self).map_err(|err| err.to_string())?;
+        let temp = path.with_extension("json.tmp");
+        fs::write(&temp, text).map_err(|err| format!("{}: {}", temp.display(), err))?;
+        fs::rename(&temp, &path).map_err(|err| format!("{}: {}", path.display(), err))
+    }
+
+    /// Deletes the library file of the given profile, if there is one.
+    pub fn delete(config_name: Option<&str>) -> Result<(), String> {
+        match Self::path(config_name) {
+            Some(path) if path.exists() => fs::remove_file(&path).map_err(|err| format!("{}: {}", path.display(), err)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Folds another library into this one. Download entries are deduplicated by
+    /// replay id and completion time; existing notes win over incoming ones.
+    pub fn merge(&mut self, other: Library) {
+        for entry in other.entries {
+            if !self
+                .entries
+                .iter()
+                .any(|e| e.replay_id == entry.replay_id && e.downloaded_at == entry.downloaded_at)
+            {
+                self.entries.push(entry);
+            }
+        }
+        for (session, note) in other.session_notes {
+            self.session_notes.entry(session).or_insert(note);
+        }
+        self.hidden.extend(other.hidden);
+        for favorite in other.favorites {
+            if !self.favorites.iter().any(|f| f._id == favorite._id) {
+                self.favorites.push(favorite);
+            }
+        }
+        for (replay_id, note) in other.notes {
+            self.notes.entry(replay_id).or_insert(note);
+        }
+        self.transferred_bytes += other.transferred_bytes;
+        self.watch_alerted.extend(other.watch_alerted);
+    }
+
+    /// Replaces the note of a replay, dropping it once it holds nothing.
+    pub fn set_note(&mut self, replay_id: &str, note: ReplayNote) {
+        if note.is_empty() {
+            self.notes.remove(replay_id);
+        } else {
+            self.notes.insert(replay_id.to_owned(), note);
+        }
+    }
+
+    /// Ids of the replays downloaded on a server. Entries recorded before servers were
+    /// tracked count for every server.
+    pub fn downloaded_on(&self, server: &str) -> HashSet<String> {
+        let server = profiles::normalize_url(server);
+        self.entries
+            .iter()
+            .filter(|e| e.server.is_empty() || profiles::normalize_url(&e.server) == server)
+            .map(|e| e.replay_id.clone())
+            .collect()
+    }
+
+    /// Every tag in use, sorted.
+    pub fn all_tags(&self) -> BTreeSet<String> {
+        self.notes.values().flat_map(|note| note.tags.iter().cloned()).collect()
+    }
+
+    /// Total bytes received by recorded downloads.
+    pub fn total_bytes(&self) -> u64 {
+        self.entries.iter().map(|e| e.bytes).sum()
+    }
+
+    /// Average speed of the downloads whose duration was recorded, in bytes per second.
+    pub fn average_speed(&self) -> Option<f64> {
+        let (bytes, seconds) = self
+            .entries
+            .iter()
+            .filter(|e| e.seconds > 0.0)
+            .fold((0u64, 0.0), |(bytes, seconds), e| (bytes + e.bytes, seconds + e.seconds));
+        (seconds > 0.0).then(|| bytes as f64 / seconds)
+    }
+
+    /// Cumulative received bytes over time as (unix timestamp, total bytes) points.
+    pub fn growth(&self) -> Vec<(u64, u64)> {
+        let mut entries: Vec<&LibraryEntry> = self.entries.iter().collect();
+        entries.sort_by_key(|e| e.downloaded_at);
+        let mut total = 0;
+        entries
+            .into_iter()
+            .map(|e| {
+                total += e.bytes;
+                (e.downloaded_at, total)
+            })
+            .collect()
+    }
+
+    /// Average bytes received per day over the last `days` days.
+    pub fn daily_rate(&self, now: u64, days: u64) -> f64 {
+        let since = now.saturating_sub(days * 86400);
+        let bytes: u64 = self
+            .entries
+            .iter()
+            .filter(|e| e.downloaded_at >= since)
+            .map(|e| e.bytes)
+            .sum();
+        bytes as f64 / days as f64
+    }
+}
+
+/// Saves libraries on one background thread, so they reach the disk in the order they were
+/// made and an older snapshot never overwrites a newer one. Snapshots that are replaced
+/// before their turn are skipped.
+pub struct Saver {
+    tx: Option<mpsc::Sender<(Option<String>, Library)>>,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+impl Saver {
+    pub fn spawn() -> Self {
+        let (tx, rx) = mpsc::channel::<(Option<String>, Library)>();
+        let thread = thread::spawn(move || {
+            let mut pending = None;
+            loop {
+                let (config_name, mut library) = match pending.take() {
+                    Some(save) => save,
+                    None => match rx.recv() {
+                        Ok(save) => save,
+                        Err(_) => break,
+                    },
+                };
+                while let Ok((name, newer)) = rx.try_recv() {
+                    if name == config_name {
+                        library = newer;
+                    } else {
+                        pending = Some((name, newer));
+                        break;
+                    }
+                }
+                if let Err(err) = library.save(config_name.as_deref()) {
+                    error::report("Error saving library", err);
+                }
+            }
+        });
+        Self { tx: Some(tx), thread: Some(thread) }
+    }
+
+    /// Queues `library` to be written as the library of profile `config_name`.
+    pub fn save(&self, config_name: Option<&str>, library: Library) {
+        if let Some(tx) = &self.tx {
+            let _ = tx.send((config_name.map(str::to_owned), library));
+        }
+    }
+
+    /// Waits until the queued saves are written, e.g. before the app exits. Later saves are
+    /// dropped.
+    pub fn finish(&mut self) {
+        self.tx = None;
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
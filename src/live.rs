@@ -0,0 +1,233 @@
+use crate::{http, ratelimit, Replay};
+use serde::Deserialize;
+use std::io::{BufRead, BufReader};
+use std::net::TcpStream;
+use std::time::{Duration, Instant};
+use tungstenite::client::IntoClientRequest;
+use tungstenite::stream::MaybeTlsStream;
+use tungstenite::{Message, WebSocket};
+
+/// Path of the live-update socket, relative to the server address.
+const LIVE_PATH: &str = "/live";
+
+/// How long a read blocks before the caller gets a chance to check its settings.
+const READ_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Like `READ_TIMEOUT` for event streams. It also bounds the wait for the response headers,
+/// so it is a little longer.
+const SSE_READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Event streams that send nothing, not even keep-alive comments, for this long are
+/// considered dead and reopened.
+const SSE_IDLE_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// One change pushed by the server. Messages are JSON text frames tagged by `event`:
+/// `{"event":"added","replay":{…}}`, `{"event":"updated","replay":{…}}` or
+/// `{"event":"expired","id":"…"}`. Anything else is ignored.
+#[derive(Debug)]
+pub enum LiveEvent {
+    Added { replay: Replay },
+    Updated { replay: Replay },
+    Expired { id: String },
+}
+
+#[derive(Deserialize)]
+struct Frame {
+    event: String,
+    #[serde(default)]
+    replay: serde_json::Value,
+    #[serde(default)]
+    id: String,
+}
+
+impl LiveEvent {
+    /// Reads a text frame; `None` for anything that is not a known event.
+    fn parse(text: &str) -> Option<Self> {
+        let frame: Frame = serde_json::from_str(text).ok()?;
+        match frame.event.as_str() {
+            "added" => Some(LiveEvent::Added { replay: Replay::from_json(&frame.replay).ok()? }),
+            "updated" => Some(LiveEvent::Updated { replay: Replay::from_json(&frame.replay).ok()? }),
+            "expired" => Some(LiveEvent::Expired { id: frame.id }),
+            _ => None,
+        }
+    }
+
+    /// Reads a Server-Sent Event: either named `added`, `updated` or `expired` with the
+    /// replay (or the id) as data, or an unnamed one carrying a frame like the socket's.
+    pub fn from_sse(name: &str, data: &str) -> Option<Self> {
+        match name {
+            "added" | "updated" => {
+                let replay = Replay::from_json(&serde_json::from_str(data).ok()?).ok()?;
+                Some(if name == "added" { LiveEvent::Added { replay } } else { LiveEvent::Updated { replay } })
+            }
+            "expired" => {
+                // A JSON string, an object with an `id`, or the bare id.
+                let id = match serde_json::from_str::<serde_json::Value>(data) {
+                    Ok(serde_json::Value::String(id)) => id,
+                    Ok(value) => value.get("id").and_then(|id| id.as_str()).unwrap_or_default().to_owned(),
+                    Err(_) => data.trim().to_owned(),
+                };
+                (!id.is_empty()).then_some(LiveEvent::Expired { id })
+            }
+            "message" => Self::parse(data),
+            _ => None,
+        }
+    }
+}
+
+/// WebSocket URL of the live-update endpoint of a server (`http` → `ws`, `https` → `wss`).
+pub fn socket_url(server_addr: &str) -> String {
+    let base = server_addr.trim_end_matches('/');
+    let base = if let Some(rest) = base.strip_prefix("https://") {
+        format!("wss://{}", rest)
+    } else if let Some(rest) = base.strip_prefix("http://") {
+        format!("ws://{}", rest)
+    } else {
+        format!("ws://{}", base)
+    };
+    format!("{}{}", base, LIVE_PATH)
+}
+
+/// An open live-update socket.
+pub struct Connection {
+    socket: WebSocket<MaybeTlsStream<TcpStream>>,
+}
+
+impl Connection {
+    /// Connects to the live-update endpoint of a server. Fails if the server has none.
+    pub fn open(server_addr: &str) -> Result<Self, String> {
+        let url = socket_url(server_addr);
+        let mut request = url.as_str().into_client_request().map_err(|err| format!("{}: {}", url, err))?;
+        request.headers_mut().extend(http::extra_headers());
+        ratelimit::SERVER.wait();
+        let (socket, _) = tungstenite::connect(request).map_err(|err| format!("{}: {}", url, err))?;
+        // Reads time out so the caller can notice a changed server or disabled live updates.
+        let stream = match socket.get_ref() {
+            MaybeTlsStream::Plain(stream) => Some(stream),
+            MaybeTlsStream::NativeTls(stream) => Some(stream.get_ref()),
+            _ => None,
+        };
+        if let Some(stream) = stream {
+            let _ = stream.set_read_timeout(Some(READ_TIMEOUT));
+        }
+        Ok(Self { socket })
+    }
+
+    /// Waits for the next event. `Ok(None)` means nothing arrived in time (or the message
+    /// was not an event); an error means the socket is gone.
+    pub fn next_event(&mut self) -> Result<Option<LiveEvent>, String> {
+        match self.socket.read() {
+            Ok(Message::Text(text)) => Ok(LiveEvent::parse(&text)),
+            Ok(Message::Close(_)) => Err("The server closed the connection".to_owned()),
+            Ok(_) => Ok(None),
+            Err(tungstenite::Error::Io(err))
+                if matches!(err.kind(), std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut) =>
+            {
+                Ok(None)
+            }
+            Err(err) => Err(err.to_string()),
+        }
+    }
+
+    pub fn close(mut self) {
+        let _ = self.socket.close(None);
+    }
+}
+
+/// An open Server-Sent Events stream.
+pub struct EventStream {
+    url: String,
+    reader: BufReader<reqwest::blocking::Response>,
+    /// Line read so far; kept across timed-out reads.
+    line: String,
+    name: String,
+    data: String,
+    last_read: Instant,
+}
+
+impl EventStream {
+    /// Requests `endpoint` of a server as an event stream. Fails unless the server answers
+    /// with a 2xx status.
+    pub fn open(server_addr: &str, endpoint: &str) -> Result<Self, String> {
+        let url = format!("{}{}", server_addr.trim_end_matches('/'), endpoint);
+        // The timeout applies to each read rather than the whole stream, which stays open.
+        let client = http::builder(server_addr)
+            .timeout(SSE_READ_TIMEOUT)
+            .build()
+            .map_err(|err| err.to_string())?;
+        ratelimit::SERVER.wait();
+        let response = client
+            .get(&url)
+            .header(reqwest::header::ACCEPT, "text/event-stream")
+            .send()
+            .and_then(|resp| resp.error_for_status())
+            .map_err(|err| format!("{}: {}", url, err))?;
+        Ok(Self {
+            url,
+            reader: BufReader::new(response),
+            line: String::new(),
+            name: String::new(),
+            data: String::new(),
+            last_read: Instant::now(),
+        })
+    }
+
+    /// Waits for the next event and returns its name (`message` if the server sends none)
+    /// and data. `Ok(None)` means nothing arrived in time; an error means the stream ended
+    /// or stayed silent for longer than `SSE_IDLE_TIMEOUT`.
+    pub fn next_event(&mut self) -> Result<Option<(String, String)>, String> {
+        loop {
+            match self.reader.read_line(&mut self.line) {
+                Ok(0) => return Err(format!("{}: the server closed the stream", self.url)),
+                Ok(_) => {
+                    self.last_read = Instant::now();
+                    let line = std::mem::take(&mut self.line);
+                    if let Some(event) = self.process_line(line.trim_end_matches(['\r', '\n'])) {
+                        return Ok(Some(event));
+                    }
+                }
+                Err(err) if is_timeout(&err) => {
+                    if self.last_read.elapsed() > SSE_IDLE_TIMEOUT {
+                        return Err(format!("{}: no data for {} seconds", self.url, SSE_IDLE_TIMEOUT.as_secs()));
+                    }
+                    return Ok(None);
+                }
+                Err(err) => return Err(format!("{}: {}", self.url, err)),
+            }
+        }
+    }
+
+    /// Handles one line of the stream, returning the event a blank line dispatches.
+    fn process_line(&mut self, line: &str) -> Option<(String, String)> {
+        if line.is_empty() {
+            let mut name = std::mem::take(&mut self.name);
+            let mut data = std::mem::take(&mut self.data);
+            if data.is_empty() {
+                return None;
+            }
+            if name.is_empty() {
+                name = "message".to_owned();
+            }
+            data.pop();
+            return Some((name, data));
+        }
+        let (field, value) = line.split_once(':').unwrap_or((line, ""));
+        let value = value.strip_prefix(' ').unwrap_or(value);
+        match field {
+            "event" => self.name = value.to_owned(),
+            "data" => {
+                self.data.push_str(value);
+                self.data.push('\n');
+            }
+            // Comments (`:` keep-alives), `id` and `retry` need no handling.
+            _ => {}
+        }
+        None
+    }
+}
+
+/// Whether a read failed because nothing arrived within the client's timeout.
+fn is_timeout(err: &std::io::Error) -> bool {
+    err.get_ref().and_then(|inner| inner.downcast_ref::<reqwest::Error>()).is_some_and(reqwest::Error::is_timeout)
+        || matches!(err.kind(), std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut)
+}
@@ -0,0 +1,135 @@
+use crate::{error, paths, profiles};
+use std::fs;
+use toml::value::Table;
+
+/// Schema version written by this build. Bump it and append a step to `STEPS` whenever a
+/// settings field is renamed, removed or changes meaning; new fields only need a default.
+pub const CURRENT_VERSION: u32 = 2;
+
+/// `STEPS[n]` upgrades a settings table from version `n` to `n + 1`.
+const STEPS: &[fn(&mut Table)] = &[from_unversioned, tls_per_server];
+
+/// Version 0 is the layout from before `config_version` existed. Every field added since
+/// then has a serde default, so only the version is stamped.
+fn from_unversioned(_settings: &mut Table) {}
+
+/// Version 2 keeps the trusted CA certificate and "accept invalid certificates" per server,
+/// so relaxing them for one server does not affect the others. The old values belong to
+/// the server in use.
+fn tls_per_server(settings: &mut Table) {
+    let ca_certificate = settings.remove("ca_certificate").and_then(|v| v.as_str().map(str::to_owned)).unwrap_or_default();
+    let accept_invalid_certs = settings.remove("accept_invalid_certs").and_then(|v| v.as_bool()).unwrap_or(false);
+    if ca_certificate.is_empty() && !accept_invalid_certs {
+        return;
+    }
+    let server = settings.get("server_addr").and_then(toml::Value::as_str).map(profiles::normalize_url).unwrap_or_default();
+    let mut tls = Table::new();
+    tls.insert("ca_certificate".to_owned(), toml::Value::String(ca_certificate));
+    tls.insert("accept_invalid_certs".to_owned(), toml::Value::Boolean(accept_invalid_certs));
+    let mut server_tls = Table::new();
+    server_tls.insert(server, toml::Value::Table(tls));
+    settings.insert("server_tls".to_owned(), toml::Value::Table(server_tls));
+}
+
+/// Version recorded in a settings table; files without one are version 0.
+fn version_of(settings: &Table) -> u32 {
+    settings.get("config_version").and_then(toml::Value::as_integer).map_or(0, |v| v.max(0) as u32)
+}
+
+/// Runs the steps from the table's version up to `CURRENT_VERSION`. Returns the version the
+/// table had, or `None` if it was already current. Tables written by a newer build are left alone.
+pub fn upgrade(settings: &mut Table) -> Option<u32> {
+    let from = version_of(settings);
+    if from >= CURRENT_VERSION {
+        return None;
+    }
+    for step in &STEPS[from as usize..] {
+        step(settings);
+    }
+    settings.insert("config_version".to_owned(), toml::Value::Integer(CURRENT_VERSION as i64));
+    Some(from)
+}
+
+/// Reads a profile's settings file and upgrades it to the current schema. A file that
+/// needed upgrading is first copied to `<profile>.v<version>.bak` next to it. Returns
+/// `Ok(None)` if the profile has no settings file yet, and whether the table was upgraded.
+pub fn load(config_name: Option<&str>) -> Result<Option<(Table, bool)>, String> {
+    let path = paths::settings_file(config_name)?;
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(format!("{}: {}", path.display(), err)),
+    };
+    let mut settings: Table = toml::from_str(&text).map_err(|err| format!("{}: {}", path.display(), err))?;
+    let Some(from) = upgrade(&mut settings) else {
+        if version_of(&settings) > CURRENT_VERSION {
+            error::report(
+                path.display(),
+                format!(
+                    "written by a newer version (settings version {}); settings this version does not know are dropped when saving",
+                    version_of(&settings)
+                ),
+            );
+        }
+        return Ok(Some((settings, false)));
+    };
+    let backup = path.with_extension(format!("v{}.bak", from));
+    fs::write(&backup, &text).map_err(|err| format!("{}: {}", backup.display(), err))?;
+    eprintln!("Upgraded {} from settings version {} to {}", path.display(), from, CURRENT_VERSION);
+    Ok(Some((settings, true)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(text: &str) -> Table {
+        toml::from_str(text).unwrap()
+    }
+
+    #[test]
+    fn upgrades_unversioned_tables() {
+        let mut settings = table("server_addr = \"http://localhost:3000\"\npage_size = 50\n");
+        assert_eq!(upgrade(&mut settings), Some(0));
+        assert_eq!(settings.get("config_version"), Some(&toml::Value::Integer(CURRENT_VERSION as i64)));
+        assert_eq!(settings.get("page_size"), Some(&toml::Value::Integer(50)));
+        assert!(!settings.contains_key("server_tls"));
+    }
+
+    #[test]
+    fn moves_tls_settings_under_the_normalized_server() {
+        let mut settings = table(
+            "config_version = 1\nserver_addr = \"HTTPS://Replays.Example:8443/\"\nca_certificate = \"/etc/ca.pem\"\naccept_invalid_certs = true\n",
+        );
+        assert_eq!(upgrade(&mut settings), Some(1));
+        assert!(!settings.contains_key("ca_certificate"));
+        assert!(!settings.contains_key("accept_invalid_certs"));
+        let expected = table("[\"https://replays.example:8443\"]\nca_certificate = \"/etc/ca.pem\"\naccept_invalid_certs = true\n");
+        assert_eq!(settings.get("server_tls"), Some(&toml::Value::Table(expected)));
+        assert_eq!(settings.get("config_version"), Some(&toml::Value::Integer(2)));
+    }
+
+    #[test]
+    fn drops_default_tls_settings_without_adding_a_server() {
+        let mut settings = table("config_version = 1\nserver_addr = \"http://localhost:3000\"\nca_certificate = \"\"\naccept_invalid_certs = false\n");
+        assert_eq!(upgrade(&mut settings), Some(1));
+        assert!(!settings.contains_key("ca_certificate"));
+        assert!(!settings.contains_key("server_tls"));
+    }
+
+    #[test]
+    fn leaves_current_tables_alone() {
+        let text = format!("config_version = {}\nserver_addr = \"http://localhost:3000\"\n", CURRENT_VERSION);
+        let mut settings = table(&text);
+        assert_eq!(upgrade(&mut settings), None);
+        assert_eq!(settings, table(&text));
+    }
+
+    #[test]
+    fn leaves_tables_from_newer_versions_alone() {
+        let text = format!("config_version = {}\nca_certificate = \"/etc/ca.pem\"\nfuture_field = true\n", CURRENT_VERSION + 1);
+        let mut settings = table(&text);
+        assert_eq!(upgrade(&mut settings), None);
+        assert_eq!(settings, table(&text));
+    }
+}
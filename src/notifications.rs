@@ -0,0 +1,16 @@
+use crate::error;
+use std::thread;
+
+/// Shows a desktop notification without blocking the UI thread.
+pub fn show(summary: String, body: String) {
+    thread::spawn(move || {
+        if let Err(err) = notify_rust::Notification::new()
+            .appname("LocalPavTV_GUI")
+            .summary(&summary)
+            .body(&body)
+            .show()
+        {
+            error::report("Error showing notification", err);
+        }
+    });
+}
@@ -0,0 +1,64 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Application name confy files are stored under.
+const APP_NAME: &str = "localpavtv_gui";
+
+/// A file with this name beside the executable turns on portable mode without `--portable`.
+const PORTABLE_MARKER: &str = "portable.txt";
+
+/// Folder beside the executable that holds config, caches and libraries in portable mode.
+const PORTABLE_DIR: &str = "LocalPavTV_GUI-data";
+
+static PORTABLE: OnceLock<Option<PathBuf>> = OnceLock::new();
+
+/// Profile started with and the settings file given for it with `--config`.
+static SETTINGS_OVERRIDE: OnceLock<Option<(Option<String>, PathBuf)>> = OnceLock::new();
+
+/// Decides where files are kept; call once at startup before anything is loaded.
+/// Portable mode is used when `portable` is set or the marker file sits beside the executable.
+/// `settings` replaces the settings file of profile `config_name` only; other profiles,
+/// caches and libraries stay in the usual place.
+pub fn init(portable: bool, config_name: Option<&str>, settings: Option<PathBuf>) {
+    let _ = SETTINGS_OVERRIDE.set(settings.map(|path| (config_name.map(str::to_owned), path)));
+    let exe_dir = std::env::current_exe().ok().and_then(|exe| exe.parent().map(Path::to_path_buf));
+    let dir = exe_dir.filter(|dir| portable || dir.join(PORTABLE_MARKER).exists()).map(|dir| dir.join(PORTABLE_DIR));
+    if let Some(dir) = &dir {
+        println!("Portable mode: storing data in {}", dir.display());
+    }
+    let _ = PORTABLE.set(dir);
+}
+
+/// Data folder beside the executable, if running in portable mode.
+pub fn portable_dir() -> Option<&'static Path> {
+    PORTABLE.get_or_init(|| None).as_deref()
+}
+
+/// Config file of a profile (`None` is confy's default profile). Everything else the
+/// app stores lives in the same directory.
+pub fn config_file(config_name: Option<&str>) -> Result<PathBuf, String> {
+    match portable_dir() {
+        Some(dir) => Ok(dir.join(format!("{}.toml", config_name.unwrap_or(crate::profiles::DEFAULT_PROFILE)))),
+        None => confy::get_configuration_file_path(APP_NAME, config_name).map_err(|err| format!("{:?}", err)),
+    }
+}
+
+/// File the settings of a profile are loaded from and saved to: `config_file`, unless
+/// `--config` named another file for it.
+pub fn settings_file(config_name: Option<&str>) -> Result<PathBuf, String> {
+    match SETTINGS_OVERRIDE.get_or_init(|| None) {
+        Some((profile, path)) if profile.as_deref() == config_name => Ok(path.clone()),
+        _ => config_file(config_name),
+    }
+}
+
+/// Loads a profile's settings file, creating it with defaults if it does not exist.
+pub fn load_config<T: Serialize + DeserializeOwned + Default>(config_name: Option<&str>) -> Result<T, String> {
+    confy::load_path(settings_file(config_name)?).map_err(|err| format!("{:?}", err))
+}
+
+pub fn store_config<T: Serialize>(config_name: Option<&str>, config: &T) -> Result<(), String> {
+    confy::store_path(settings_file(config_name)?, config).map_err(|err| format!("{:?}", err))
+}
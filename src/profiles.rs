@@ -0,0 +1,106 @@
+use crate::library::Library;
+use crate::paths;
+use crate::secrets;
+use crate::Settings;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::PathBuf;
+
+/// File name confy uses when no profile name is given.
+pub const DEFAULT_PROFILE: &str = "default-config";
+
+/// Maps a profile name to the `config_name` argument confy expects.
+pub fn config_arg(profile: &str) -> Option<&str> {
+    (profile != DEFAULT_PROFILE).then_some(profile)
+}
+
+/// Lists all profiles that have a config file in the confy directory.
+pub fn list() -> Vec<String> {
+    let Ok(path) = paths::config_file(None) else {
+        return Vec::new();
+    };
+    let (Some(dir), Some(extension)) = (path.parent(), path.extension()) else {
+        return Vec::new();
+    };
+    let mut profiles: Vec<String> = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|p| p.extension() == Some(extension))
+        .filter_map(|p| p.file_stem().map(|s| s.to_string_lossy().into_owned()))
+        .collect();
+    profiles.sort();
+    profiles
+}
+
+/// Normalizes a server address so that e.g. `HTTP://Server:3000/` and `http://server:3000` compare equal.
+pub fn normalize_url(url: &str) -> String {
+    let url = url.trim().trim_end_matches('/');
+    match url.split_once("://") {
+        Some((scheme, rest)) => {
+            let (host, path) = rest.split_once('/').map_or((rest, ""), |(h, p)| (h, p));
+            let path = if path.is_empty() { String::new() } else { format!("/{}", path) };
+            format!("{}://{}{}", scheme.to_lowercase(), host.to_lowercase(), path)
+        }
+        None => url.to_lowercase(),
+    }
+}
+
+/// Normalized server addresses of all profiles.
+pub fn servers() -> BTreeSet<String> {
+    list()
+        .iter()
+        .filter_map(|profile| paths::load_config::<Settings>(config_arg(profile)).ok())
+        .map(|settings| normalize_url(&settings.server_addr))
+        .collect()
+}
+
+/// Groups of profiles whose server addresses point at the same server, keyed by normalized address.
+pub fn find_duplicates() -> BTreeMap<String, Vec<String>> {
+    let mut by_server: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for profile in list() {
+        if let Ok(settings) = paths::load_config::<Settings>(config_arg(&profile)) {
+            by_server.entry(normalize_url(&settings.server_addr)).or_default().push(profile);
+        }
+    }
+    by_server.retain(|_, profiles| profiles.len() > 1);
+    by_server
+}
+
+/// Files that merging into `target` deletes: the config and library files of the other
+/// profiles in `sources`.
+pub fn merge_deletes(target: &str, sources: &[String]) -> Vec<PathBuf> {
+    sources
+        .iter()
+        .filter(|s| s.as_str() != target)
+        .flat_map(|source| [paths::config_file(config_arg(source)).ok(), Library::path(config_arg(source))])
+        .flatten()
+        .filter(|path| path.exists())
+        .collect()
+}
+
+/// Merges the libraries of `sources` into `target` and deletes the source profiles
+/// (config and library files and keyring secrets). Nothing is changed if any of them
+/// cannot be read. Returns the merged library of the target.
+pub fn merge(target: &str, sources: &[String]) -> Result<Library, String> {
+    let mut library = Library::try_load(config_arg(target))?;
+    let mut loaded = Vec::new();
+    for source in sources.iter().filter(|s| s.as_str() != target) {
+        let settings = paths::load_config::<Settings>(config_arg(source)).map_err(|err| format!("{}: {}", source, err))?;
+        loaded.push((source, settings, Library::try_load(config_arg(source))?));
+    }
+    for (_, _, source_library) in &mut loaded {
+        library.merge(std::mem::take(source_library));
+    }
+    library.save(config_arg(target))?;
+    for (source, mut settings, _) in loaded {
+        for (field, _) in settings.secret_fields_mut() {
+            secrets::store(config_arg(source), &field, "")?;
+        }
+        let config = paths::config_file(config_arg(source))?;
+        fs::remove_file(&config).map_err(|err| format!("{}: {}", config.display(), err))?;
+        Library::delete(config_arg(source))?;
+    }
+    Ok(library)
+}
@@ -0,0 +1,78 @@
+//! Push notifications to phones through an ntfy or Gotify server.
+
+use crate::aliases::Aliases;
+use crate::events::{EventSender, Subsystem};
+use crate::i18n::trf;
+use crate::{error, Replay};
+use serde::{Deserialize, Serialize};
+use std::thread;
+use std::time::Duration;
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Service {
+    Off,
+    Ntfy,
+    Gotify,
+}
+
+/// Where pushes go.
+#[derive(Clone)]
+pub struct Config {
+    pub service: Service,
+    /// Base URL, e.g. `https://ntfy.sh`.
+    pub server: String,
+    /// ntfy topic; unused by Gotify.
+    pub topic: String,
+    /// Gotify application token, or ntfy access token (empty for public topics).
+    pub token: String,
+}
+
+/// Whether `filter` (empty = anything) occurs in the replay name, a player id or a
+/// nickname, ignoring case.
+pub fn matches(filter: &str, replay: &Replay, aliases: &Aliases) -> bool {
+    let filter = filter.trim().to_lowercase();
+    filter.is_empty()
+        || replay.friendlyName.to_lowercase().contains(&filter)
+        || replay.users.iter().any(|user| {
+            user.to_lowercase().contains(&filter) || aliases.get(user).is_some_and(|name| name.to_lowercase().contains(&filter))
+        })
+}
+
+/// Sends a push and waits for the server to accept it.
+pub fn send_blocking(config: &Config, title: &str, message: &str) -> Result<(), String> {
+    let client = reqwest::blocking::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build()
+        .map_err(|err| err.to_string())?;
+    let server = config.server.trim().trim_end_matches('/');
+    let request = match config.service {
+        Service::Off => return Ok(()),
+        Service::Ntfy => {
+            let request = client.post(format!("{}/{}", server, config.topic.trim())).header("Title", title).body(message.to_owned());
+            if config.token.is_empty() {
+                request
+            } else {
+                request.bearer_auth(&config.token)
+            }
+        }
+        Service::Gotify => client
+            .post(format!("{}/message", server))
+            .header("X-Gotify-Key", &config.token)
+            .json(&serde_json::json!({ "title": title, "message": message, "priority": 5 })),
+    };
+    let response = request.send().map_err(|err| err.to_string())?;
+    if response.status().is_success() {
+        Ok(())
+    } else {
+        Err(format!("{} answered {}", server, response.status()))
+    }
+}
+
+/// Sends a push without blocking the UI thread. Deliveries are logged to `events`,
+/// failures are reported.
+pub fn send(config: Config, title: String, message: String, events: EventSender) {
+    thread::spawn(move || match send_blocking(&config, &title, &message) {
+        Ok(()) => events.send(Subsystem::Webhook, trf("Sent push notification \"{}\" to {}", &[&title, &config.server.trim()])),
+        Err(err) => error::report("Error sending push notification", err),
+    });
+}
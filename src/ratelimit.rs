@@ -0,0 +1,48 @@
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Requests to the replay server, from every thread.
+pub static SERVER: Limiter = Limiter::new();
+
+/// Avatar downloads from the CDN (or the configured avatar source).
+pub static CDN: Limiter = Limiter::new();
+
+struct State {
+    /// Requests allowed per second; 0 means unlimited.
+    per_second: f64,
+    /// Earliest time the next request may start.
+    next: Option<Instant>,
+}
+
+/// Spaces requests evenly so bulk operations (loading all pages, avatars of a long list)
+/// stay below a rate that server-side bans or CDN throttling would react to.
+pub struct Limiter {
+    state: Mutex<State>,
+}
+
+impl Limiter {
+    const fn new() -> Self {
+        Self { state: Mutex::new(State { per_second: 0.0, next: None }) }
+    }
+
+    pub fn set_rate(&self, per_second: f64) {
+        self.state.lock().unwrap().per_second = per_second.max(0.0);
+    }
+
+    /// Blocks until the caller may send its request. Each caller reserves its own slot
+    /// first, so concurrent threads queue up instead of all firing when the limit frees up.
+    pub fn wait(&self) {
+        let slot = {
+            let mut state = self.state.lock().unwrap();
+            if state.per_second <= 0.0 {
+                return;
+            }
+            let now = Instant::now();
+            let slot = state.next.map_or(now, |next| next.max(now));
+            state.next = Some(slot + Duration::from_secs_f64(1.0 / state.per_second));
+            slot
+        };
+        thread::sleep(slot.saturating_duration_since(Instant::now()));
+    }
+}
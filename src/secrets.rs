@@ -0,0 +1,33 @@
+use keyring::Entry;
+
+/// Service name under which all secrets are stored in the OS keyring.
+const SERVICE: &str = "localpavtv_gui";
+
+/// Builds the keyring entry for a secret field of the given confy profile.
+fn entry(config_name: Option<&str>, field: &str) -> keyring::Result<Entry> {
+    let profile = config_name.unwrap_or("default-config");
+    Entry::new(SERVICE, &format!("{}:{}", profile, field))
+}
+
+/// Reads a secret from the keyring. Missing entries are returned as an empty string.
+pub fn load(config_name: Option<&str>, field: &str) -> Result<String, String> {
+    match entry(config_name, field).and_then(|e| e.get_password()) {
+        Ok(value) => Ok(value),
+        Err(keyring::Error::NoEntry) => Ok(String::new()),
+        Err(err) => Err(format!("Error reading secret {} from keyring: {}", field, err)),
+    }
+}
+
+/// Writes a secret to the keyring, deleting the entry when the value is empty.
+pub fn store(config_name: Option<&str>, field: &str, value: &str) -> Result<(), String> {
+    let entry = entry(config_name, field).map_err(|err| format!("Error opening keyring for {}: {}", field, err))?;
+    let result = if value.is_empty() {
+        match entry.delete_credential() {
+            Err(keyring::Error::NoEntry) => Ok(()),
+            other => other,
+        }
+    } else {
+        entry.set_password(value)
+    };
+    result.map_err(|err| format!("Error writing secret {} to keyring: {}", field, err))
+}
@@ -0,0 +1,190 @@
+//! Keyboard shortcuts for the main actions, remappable in Settings.
+
+use eframe::egui::{self, Key, KeyboardShortcut, Modifiers};
+use serde::{Deserialize, Serialize};
+
+/// Something a shortcut can do.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Action {
+    Refresh,
+    FocusSearch,
+    PreviousPage,
+    NextPage,
+    DownloadSelected,
+}
+
+impl Action {
+    pub const ALL: [Action; 5] = [Action::Refresh, Action::FocusSearch, Action::PreviousPage, Action::NextPage, Action::DownloadSelected];
+
+    /// English name shown in Settings.
+    pub fn label(self) -> &'static str {
+        match self {
+            Action::Refresh => "Refresh the replay list",
+            Action::FocusSearch => "Focus the user filter",
+            Action::PreviousPage => "Previous page",
+            Action::NextPage => "Next page / load more",
+            Action::DownloadSelected => "Download the selected replay",
+        }
+    }
+}
+
+/// A key with modifiers, stored as text like `Ctrl+Shift+F5`. `Ctrl` stands for Cmd on macOS.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Shortcut(pub KeyboardShortcut);
+
+impl Shortcut {
+    const fn new(modifiers: Modifiers, key: Key) -> Self {
+        Shortcut(KeyboardShortcut::new(modifiers, key))
+    }
+
+    /// How the shortcut reads on this platform, e.g. `⌘F` on macOS.
+    pub fn format(&self, ctx: &egui::Context) -> String {
+        ctx.format_shortcut(&self.0)
+    }
+
+    /// Number of modifiers held; more specific shortcuts are checked first.
+    fn modifier_count(&self) -> usize {
+        let m = self.0.modifiers;
+        [m.alt, m.shift, m.command || m.ctrl || m.mac_cmd].iter().filter(|held| **held).count()
+    }
+}
+
+impl TryFrom<String> for Shortcut {
+    type Error = String;
+
+    fn try_from(text: String) -> Result<Self, String> {
+        let mut modifiers = Modifiers::NONE;
+        let mut parts: Vec<&str> = text.split('+').map(str::trim).collect();
+        let key_name = parts.pop().unwrap_or_default();
+        for part in parts {
+            match part.to_ascii_lowercase().as_str() {
+                "ctrl" | "cmd" | "command" => modifiers |= Modifiers::COMMAND,
+                "shift" => modifiers |= Modifiers::SHIFT,
+                "alt" | "option" => modifiers |= Modifiers::ALT,
+                _ => return Err(format!("Unknown modifier `{}` in shortcut `{}`", part, text)),
+            }
+        }
+        let key = Key::from_name(key_name).ok_or_else(|| format!("Unknown key in shortcut `{}`", text))?;
+        Ok(Shortcut::new(modifiers, key))
+    }
+}
+
+impl From<Shortcut> for String {
+    fn from(shortcut: Shortcut) -> String {
+        let KeyboardShortcut { modifiers, logical_key } = shortcut.0;
+        let mut text = String::new();
+        if modifiers.command || modifiers.ctrl || modifiers.mac_cmd {
+            text.push_str("Ctrl+");
+        }
+        if modifiers.shift {
+            text.push_str("Shift+");
+        }
+        if modifiers.alt {
+            text.push_str("Alt+");
+        }
+        text.push_str(logical_key.name());
+        text
+    }
+}
+
+/// Shortcut of every action; `None` leaves an action without one.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Bindings {
+    pub refresh: Option<Shortcut>,
+    pub focus_search: Option<Shortcut>,
+    pub previous_page: Option<Shortcut>,
+    pub next_page: Option<Shortcut>,
+    pub download_selected: Option<Shortcut>,
+}
+
+impl Default for Bindings {
+    fn default() -> Self {
+        Self {
+            refresh: Some(Shortcut::new(Modifiers::NONE, Key::F5)),
+            focus_search: Some(Shortcut::new(Modifiers::COMMAND, Key::F)),
+            previous_page: Some(Shortcut::new(Modifiers::NONE, Key::PageUp)),
+            next_page: Some(Shortcut::new(Modifiers::NONE, Key::PageDown)),
+            download_selected: Some(Shortcut::new(Modifiers::COMMAND, Key::D)),
+        }
+    }
+}
+
+impl Bindings {
+    pub fn get(&self, action: Action) -> Option<Shortcut> {
+        *match action {
+            Action::Refresh => &self.refresh,
+            Action::FocusSearch => &self.focus_search,
+            Action::PreviousPage => &self.previous_page,
+            Action::NextPage => &self.next_page,
+            Action::DownloadSelected => &self.download_selected,
+        }
+    }
+
+    pub fn set(&mut self, action: Action, shortcut: Option<Shortcut>) {
+        let slot = match action {
+            Action::Refresh => &mut self.refresh,
+            Action::FocusSearch => &mut self.focus_search,
+            Action::PreviousPage => &mut self.previous_page,
+            Action::NextPage => &mut self.next_page,
+            Action::DownloadSelected => &mut self.download_selected,
+        };
+        *slot = shortcut;
+    }
+
+    /// Another action bound to the same shortcut as `action`, if any.
+    pub fn conflict(&self, action: Action) -> Option<Action> {
+        let shortcut = self.get(action)?;
+        Action::ALL.into_iter().find(|other| *other != action && self.get(*other) == Some(shortcut))
+    }
+
+    /// Consumes the key presses of bound shortcuts and returns their actions. Shortcuts
+    /// without Ctrl or Alt are left to the text field being typed in, if there is one.
+    pub fn pressed(&self, ctx: &egui::Context) -> Vec<Action> {
+        let typing = ctx.wants_keyboard_input();
+        let mut bound: Vec<(Action, Shortcut)> = Action::ALL
+            .into_iter()
+            .filter_map(|action| self.get(action).map(|shortcut| (action, shortcut)))
+            .filter(|(_, shortcut)| !typing || shortcut.0.modifiers.command || shortcut.0.modifiers.alt)
+            .collect();
+        // Ctrl+Shift+D must not also count as Ctrl+D.
+        bound.sort_by_key(|(_, shortcut)| std::cmp::Reverse(shortcut.modifier_count()));
+        ctx.input_mut(|i| bound.into_iter().filter(|(_, shortcut)| i.consume_shortcut(&shortcut.0)).map(|(action, _)| action).collect())
+    }
+}
+
+/// Result of waiting for a new shortcut in Settings.
+pub enum Capture {
+    Waiting,
+    Cancelled,
+    Pressed(Shortcut),
+}
+
+/// Takes the next key press as a new shortcut; Escape cancels.
+pub fn capture(ctx: &egui::Context) -> Capture {
+    ctx.input_mut(|i| {
+        let pressed = i.events.iter().find_map(|event| match event {
+            egui::Event::Key { key, pressed: true, repeat: false, modifiers, .. } => Some((*key, *modifiers)),
+            _ => None,
+        });
+        let Some((key, modifiers)) = pressed else {
+            return Capture::Waiting;
+        };
+        i.events.retain(|event| !matches!(event, egui::Event::Key { .. }));
+        if key == Key::Escape && modifiers.is_none() {
+            return Capture::Cancelled;
+        }
+        let mut kept = Modifiers::NONE;
+        if modifiers.command || modifiers.ctrl {
+            kept |= Modifiers::COMMAND;
+        }
+        if modifiers.shift {
+            kept |= Modifiers::SHIFT;
+        }
+        if modifiers.alt {
+            kept |= Modifiers::ALT;
+        }
+        Capture::Pressed(Shortcut::new(kept, key))
+    })
+}
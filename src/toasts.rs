@@ -0,0 +1,88 @@
+use eframe::egui;
+use std::time::{Duration, Instant};
+
+/// More toasts than this push out the oldest.
+const MAX_TOASTS: usize = 5;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Kind {
+    Success,
+    Info,
+    Error,
+}
+
+impl Kind {
+    fn color(self) -> egui::Color32 {
+        match self {
+            Kind::Success => egui::Color32::from_rgb(60, 160, 80),
+            Kind::Info => egui::Color32::from_rgb(70, 120, 190),
+            Kind::Error => egui::Color32::from_rgb(210, 60, 60),
+        }
+    }
+}
+
+struct Toast {
+    kind: Kind,
+    message: String,
+    shown: Instant,
+}
+
+/// Transient notifications stacked in the bottom-right corner. They disappear after a
+/// timeout or when dismissed, and never block the rest of the window.
+pub struct Toasts {
+    toasts: Vec<Toast>,
+}
+
+impl Toasts {
+    pub fn new() -> Self {
+        Self { toasts: Vec::new() }
+    }
+
+    pub fn push(&mut self, kind: Kind, message: impl Into<String>) {
+        self.toasts.push(Toast { kind, message: message.into(), shown: Instant::now() });
+        if self.toasts.len() > MAX_TOASTS {
+            self.toasts.remove(0);
+        }
+    }
+
+    /// Draws the toasts, dropping expired ones. A `timeout` of zero keeps them until dismissed;
+    /// hovering a toast keeps it open.
+    pub fn show(&mut self, ctx: &egui::Context, timeout: Duration) {
+        if !timeout.is_zero() {
+            self.toasts.retain(|toast| toast.shown.elapsed() < timeout);
+        }
+        if self.toasts.is_empty() {
+            return;
+        }
+        let mut dismissed = None;
+        egui::Area::new(egui::Id::new("toasts"))
+            .anchor(egui::Align2::RIGHT_BOTTOM, [-12.0, -48.0])
+            .order(egui::Order::Foreground)
+            .show(ctx, |ui| {
+                ui.set_max_width(340.0);
+                for (index, toast) in self.toasts.iter_mut().enumerate() {
+                    let response = egui::Frame::popup(ui.style())
+                        .stroke(egui::Stroke::new(1.0, toast.kind.color()))
+                        .show(ui, |ui| {
+                            ui.horizontal(|ui| {
+                                let dismiss = crate::label_for_screen_readers(ui.small_button("✕"), crate::i18n::tr("Dismiss"));
+                                if dismiss.on_hover_text(crate::i18n::tr("Dismiss")).clicked() {
+                                    dismissed = Some(index);
+                                }
+                                ui.add(egui::Label::new(egui::RichText::new(&toast.message).color(toast.kind.color())).wrap());
+                            });
+                        })
+                        .response;
+                    if response.contains_pointer() {
+                        toast.shown = Instant::now();
+                    }
+                }
+            });
+        if let Some(index) = dismissed {
+            self.toasts.remove(index);
+        }
+        if let Some(oldest) = self.toasts.iter().map(|toast| toast.shown).min().filter(|_| !timeout.is_zero()) {
+            ctx.request_repaint_after(timeout.saturating_sub(oldest.elapsed()));
+        }
+    }
+}
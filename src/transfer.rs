@@ -0,0 +1,90 @@
+//! Progress of the downloads in flight and the bytes transferred since the app started.
+
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// How long the current speed is averaged over.
+const RATE_WINDOW: Duration = Duration::from_secs(1);
+
+static ACTIVE: Mutex<BTreeMap<String, Progress>> = Mutex::new(BTreeMap::new());
+
+static SESSION_BYTES: AtomicU64 = AtomicU64::new(0);
+
+/// A download in flight.
+#[derive(Clone)]
+pub struct Progress {
+    pub bytes: u64,
+    /// Bytes per second over the last `RATE_WINDOW`.
+    pub rate: f64,
+    window_start: Instant,
+    window_bytes: u64,
+}
+
+/// Sink the response body of a download is copied into; keeps its `Progress` up to date
+/// until dropped.
+pub struct Tracker {
+    replay_id: String,
+    started: Instant,
+    bytes: u64,
+}
+
+impl Tracker {
+    pub fn start(replay_id: &str) -> Self {
+        let now = Instant::now();
+        let progress = Progress { bytes: 0, rate: 0.0, window_start: now, window_bytes: 0 };
+        ACTIVE.lock().unwrap().insert(replay_id.to_owned(), progress);
+        Self { replay_id: replay_id.to_owned(), started: now, bytes: 0 }
+    }
+
+    /// Time since the download started.
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    /// Bytes received so far.
+    pub fn bytes(&self) -> u64 {
+        self.bytes
+    }
+}
+
+impl Write for Tracker {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let len = buf.len() as u64;
+        self.bytes += len;
+        SESSION_BYTES.fetch_add(len, Ordering::Relaxed);
+        if let Some(progress) = ACTIVE.lock().unwrap().get_mut(&self.replay_id) {
+            progress.bytes += len;
+            progress.window_bytes += len;
+            let window = progress.window_start.elapsed();
+            if window >= RATE_WINDOW {
+                progress.rate = progress.window_bytes as f64 / window.as_secs_f64();
+                progress.window_start = Instant::now();
+                progress.window_bytes = 0;
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for Tracker {
+    fn drop(&mut self) {
+        ACTIVE.lock().unwrap().remove(&self.replay_id);
+    }
+}
+
+/// Downloads in flight by replay id.
+pub fn active() -> Vec<(String, Progress)> {
+    ACTIVE.lock().unwrap().iter().map(|(id, progress)| (id.clone(), progress.clone())).collect()
+}
+
+/// Bytes received by downloads since the app started, including failed ones.
+pub fn session_bytes() -> u64 {
+    SESSION_BYTES.load(Ordering::Relaxed)
+}
@@ -0,0 +1,106 @@
+use eframe::egui;
+use std::sync::mpsc;
+use tray_icon::menu::{CheckMenuItem, Menu, MenuEvent, MenuId, MenuItem, PredefinedMenuItem};
+use tray_icon::{Icon, TrayIcon, TrayIconBuilder};
+
+/// Something picked from the tray menu.
+pub enum Command {
+    ToggleWindow,
+    Refresh,
+    ToggleMiniMode,
+    ToggleAutoDownload,
+    Quit,
+}
+
+/// Ids of the menu entries, to map menu events to commands.
+struct Ids {
+    show: MenuId,
+    refresh: MenuId,
+    mini: MenuId,
+    pause: MenuId,
+    quit: MenuId,
+}
+
+/// The tray icon and the commands picked from its menu.
+pub struct Tray {
+    commands: mpsc::Receiver<Command>,
+    /// The icon disappears when dropped. On Linux it lives on the GTK thread instead.
+    _icon: Option<TrayIcon>,
+}
+
+fn build(icon: Icon) -> Result<(TrayIcon, Ids), String> {
+    let show = MenuItem::new("Show/Hide", true, None);
+    let refresh = MenuItem::new("Refresh now", true, None);
+    let mini = MenuItem::new("Mini mode on/off", true, None);
+    let pause = CheckMenuItem::new("Pause auto-download", true, false, None);
+    let quit = MenuItem::new("Quit", true, None);
+    let menu = Menu::new();
+    menu.append_items(&[&show, &refresh, &mini, &pause, &PredefinedMenuItem::separator(), &quit])
+        .map_err(|err| err.to_string())?;
+    let tray = TrayIconBuilder::new()
+        .with_menu(Box::new(menu))
+        .with_tooltip("LocalPavTV_GUI")
+        .with_icon(icon)
+        .build()
+        .map_err(|err| err.to_string())?;
+    let ids = Ids { show: show.id().clone(), refresh: refresh.id().clone(), mini: mini.id().clone(), pause: pause.id().clone(), quit: quit.id().clone() };
+    Ok((tray, ids))
+}
+
+/// Adds the tray icon. Menu clicks wake the UI through `ctx`, so they are handled even
+/// while the window is hidden.
+pub fn create(ctx: &egui::Context) -> Result<Tray, String> {
+    let image = crate::avatars::identicon("LocalPavTV_GUI");
+    let rgba = image.pixels.iter().flat_map(|pixel| pixel.to_array()).collect();
+    let icon = Icon::from_rgba(rgba, image.size[0] as u32, image.size[1] as u32).map_err(|err| err.to_string())?;
+
+    // tray-icon needs a running GTK main loop on Linux, which eframe does not provide.
+    #[cfg(target_os = "linux")]
+    let (ids, tray) = {
+        let (ids_tx, ids_rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            if let Err(err) = gtk::init() {
+                let _ = ids_tx.send(Err(err.to_string()));
+                return;
+            }
+            match build(icon) {
+                Ok((_tray, ids)) => {
+                    let _ = ids_tx.send(Ok(ids));
+                    gtk::main();
+                }
+                Err(err) => {
+                    let _ = ids_tx.send(Err(err));
+                }
+            }
+        });
+        (ids_rx.recv().map_err(|err| err.to_string())??, None)
+    };
+    #[cfg(not(target_os = "linux"))]
+    let (ids, tray) = {
+        let (tray, ids) = build(icon)?;
+        (ids, Some(tray))
+    };
+
+    let (tx, commands) = mpsc::channel();
+    let ctx = ctx.clone();
+    MenuEvent::set_event_handler(Some(move |event: MenuEvent| {
+        let command = match event.id {
+            id if id == ids.show => Command::ToggleWindow,
+            id if id == ids.refresh => Command::Refresh,
+            id if id == ids.mini => Command::ToggleMiniMode,
+            id if id == ids.pause => Command::ToggleAutoDownload,
+            id if id == ids.quit => Command::Quit,
+            _ => return,
+        };
+        let _ = tx.send(command);
+        ctx.request_repaint();
+    }));
+    Ok(Tray { commands, _icon: tray })
+}
+
+impl Tray {
+    /// Next command picked from the menu, if any.
+    pub fn poll(&self) -> Option<Command> {
+        self.commands.try_recv().ok()
+    }
+}
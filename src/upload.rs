@@ -0,0 +1,88 @@
+//! Uploads of local `.replay` files to servers that accept them (the `upload` feature).
+
+use crate::events::{EventSender, Subsystem};
+use crate::i18n::trf;
+use crate::{format_bytes, http, ratelimit};
+use reqwest::blocking::Body;
+use reqwest::header::CONTENT_TYPE;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, Cursor, Read};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+static ACTIVE: Mutex<BTreeMap<String, Progress>> = Mutex::new(BTreeMap::new());
+
+/// An upload in flight.
+#[derive(Clone)]
+pub struct Progress {
+    pub sent: u64,
+    /// Size of the whole request body.
+    pub total: u64,
+}
+
+/// Request body that counts what reqwest has read of it; its `Progress` is removed when dropped.
+struct Tracked<R> {
+    inner: R,
+    file_name: String,
+}
+
+impl<R: Read> Read for Tracked<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        if let Some(progress) = ACTIVE.lock().unwrap().get_mut(&self.file_name) {
+            progress.sent += read as u64;
+        }
+        Ok(read)
+    }
+}
+
+impl<R> Drop for Tracked<R> {
+    fn drop(&mut self) {
+        ACTIVE.lock().unwrap().remove(&self.file_name);
+    }
+}
+
+/// Uploads in flight by file name.
+pub fn active() -> Vec<(String, Progress)> {
+    ACTIVE.lock().unwrap().iter().map(|(name, progress)| (name.clone(), progress.clone())).collect()
+}
+
+/// Whether a dropped file looks like a replay.
+pub fn is_replay(path: &Path) -> bool {
+    path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("replay"))
+}
+
+/// POSTs a replay file to `/upload` as the `replay` field of a multipart form. The body is
+/// streamed from disk, so large files neither sit in memory nor hide their progress.
+/// The start and a successful end are logged to `events`; failures are left to the caller.
+pub fn upload(server_addr: &str, path: &Path, events: &EventSender) -> Result<(), String> {
+    let file_name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+    let file = File::open(path).map_err(|err| format!("{}: {}", path.display(), err))?;
+    let len = file.metadata().map_err(|err| format!("{}: {}", path.display(), err))?.len();
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_nanos());
+    let boundary = format!("LocalPavTV{:x}", nanos);
+    // Quotes and line breaks would end the header early.
+    let quoted_name: String = file_name.chars().filter(|c| !matches!(c, '"' | '\r' | '\n')).collect();
+    let head = format!(
+        "--{}\r\nContent-Disposition: form-data; name=\"replay\"; filename=\"{}\"\r\nContent-Type: application/octet-stream\r\n\r\n",
+        boundary, quoted_name
+    );
+    let tail = format!("\r\n--{}--\r\n", boundary);
+    let total = head.len() as u64 + len + tail.len() as u64;
+
+    events.send(Subsystem::Uploads, trf("Uploading {} to {}", &[&file_name, &server_addr]));
+    ACTIVE.lock().unwrap().insert(file_name.clone(), Progress { sent: 0, total });
+    let body = Tracked { inner: Cursor::new(head).chain(file).chain(Cursor::new(tail)), file_name: file_name.clone() };
+    ratelimit::SERVER.wait();
+    http::download_client(server_addr)
+        .post(format!("{}/upload", server_addr))
+        .header(CONTENT_TYPE, format!("multipart/form-data; boundary={}", boundary))
+        .body(Body::sized(body, total))
+        .send()
+        .and_then(|resp| resp.error_for_status())
+        .map_err(|err| err.to_string())?;
+    events.send(Subsystem::Uploads, trf("Uploaded {} to {} ({})", &[&file_name, &server_addr, &format_bytes(len)]));
+    Ok(())
+}
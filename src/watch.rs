@@ -0,0 +1,57 @@
+//! "Watch in Pavlov": hands a replay to the game, either as text to paste into its console
+//! or by running a launch command.
+
+use std::process::Command;
+
+/// Placeholders the templates understand.
+pub const PLACEHOLDERS: &str = "{id}, {server}, {name}";
+
+/// Values substituted into the templates.
+pub struct Fields<'a> {
+    pub replay_id: &'a str,
+    pub server: &'a str,
+    pub name: &'a str,
+}
+
+/// Fills the placeholders of a template.
+pub fn render(template: &str, fields: &Fields) -> String {
+    template.replace("{id}", fields.replay_id).replace("{server}", fields.server).replace("{name}", fields.name)
+}
+
+/// Splits a command line at spaces outside double quotes.
+fn split_args(command: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    let mut started = false;
+    for c in command.chars() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                started = true;
+            }
+            c if c.is_whitespace() && !quoted => {
+                if started {
+                    args.push(std::mem::take(&mut current));
+                    started = false;
+                }
+            }
+            c => {
+                current.push(c);
+                started = true;
+            }
+        }
+    }
+    if started {
+        args.push(current);
+    }
+    args
+}
+
+/// Starts the launch command without waiting for it. Placeholders are filled in per
+/// argument and no shell is involved, so names from the server cannot inject commands.
+pub fn launch(template: &str, fields: &Fields) -> Result<(), String> {
+    let args: Vec<String> = split_args(template).iter().map(|arg| render(arg, fields)).collect();
+    let (program, args) = args.split_first().ok_or("The launch command is empty")?;
+    Command::new(program).args(args).spawn().map(drop).map_err(|err| format!("{}: {}", program, err))
+}
@@ -0,0 +1,122 @@
+//! Generic outbound webhook: an HTTP request with a JSON body rendered from a template,
+//! sent when a new replay shows up and when a download finishes or fails.
+
+use crate::events::{EventSender, Subsystem};
+use crate::i18n::trf;
+use crate::{error, Replay};
+use std::thread;
+use std::time::Duration;
+
+/// Methods offered in Settings.
+pub const METHODS: &[&str] = &["POST", "PUT", "PATCH"];
+
+pub const DEFAULT_TEMPLATE: &str = r#"{"event": "{event}", "replay_id": "{replay_id}", "name": "{name}", "game_mode": "{game_mode}", "server": "{server}", "users": "{users}", "message": "{message}"}"#;
+
+/// Placeholders the template may use, for the Settings hint.
+pub const PLACEHOLDERS: &str = "{event} {replay_id} {name} {game_mode} {workshop_id} {server} {users} {message}";
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Event {
+    NewReplay,
+    DownloadComplete,
+    DownloadFailed,
+}
+
+impl Event {
+    /// Value of the `{event}` placeholder.
+    pub fn name(self) -> &'static str {
+        match self {
+            Event::NewReplay => "new_replay",
+            Event::DownloadComplete => "download_complete",
+            Event::DownloadFailed => "download_failed",
+        }
+    }
+}
+
+/// Where and how the webhook is sent.
+#[derive(Clone)]
+pub struct Config {
+    pub url: String,
+    pub method: String,
+    pub template: String,
+}
+
+/// Values for the placeholders of one event.
+#[derive(Clone, Default)]
+pub struct Fields {
+    pub replay_id: String,
+    pub name: String,
+    pub game_mode: String,
+    pub workshop_id: String,
+    pub server: String,
+    pub users: Vec<String>,
+    pub message: String,
+}
+
+impl Fields {
+    /// Fields of a replay, if it is known, or just its id.
+    pub fn new(replay_id: &str, replay: Option<&Replay>, server: &str, message: impl Into<String>) -> Self {
+        Self {
+            replay_id: replay_id.to_owned(),
+            name: replay.map(|r| r.friendlyName.clone()).unwrap_or_default(),
+            game_mode: replay.map(|r| r.gameMode.clone()).unwrap_or_default(),
+            workshop_id: replay.map(|r| r.workshop_id.clone()).unwrap_or_default(),
+            server: server.to_owned(),
+            users: replay.map(|r| r.users.clone()).unwrap_or_default(),
+            message: message.into(),
+        }
+    }
+}
+
+/// Escapes a value for use inside a JSON string literal of the template.
+fn escape(value: &str) -> String {
+    let quoted = serde_json::to_string(value).unwrap_or_default();
+    quoted[1..quoted.len() - 1].to_owned()
+}
+
+/// The template with every placeholder replaced by its JSON-escaped value.
+pub fn render(template: &str, event: Event, fields: &Fields) -> String {
+    let values = [
+        ("{event}", event.name().to_owned()),
+        ("{replay_id}", fields.replay_id.clone()),
+        ("{name}", fields.name.clone()),
+        ("{game_mode}", fields.game_mode.clone()),
+        ("{workshop_id}", fields.workshop_id.clone()),
+        ("{server}", fields.server.clone()),
+        ("{users}", fields.users.join(", ")),
+        ("{message}", fields.message.clone()),
+    ];
+    values
+        .iter()
+        .fold(template.to_owned(), |text, (placeholder, value)| text.replace(placeholder, &escape(value)))
+}
+
+/// Sends the webhook and waits for the answer.
+pub fn send_blocking(config: &Config, event: Event, fields: &Fields) -> Result<(), String> {
+    let method = reqwest::Method::from_bytes(config.method.trim().to_uppercase().as_bytes())
+        .map_err(|_| format!("`{}` is not an HTTP method", config.method))?;
+    let client = reqwest::blocking::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build()
+        .map_err(|err| err.to_string())?;
+    let response = client
+        .request(method, config.url.trim())
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .body(render(&config.template, event, fields))
+        .send()
+        .map_err(|err| err.to_string())?;
+    if response.status().is_success() {
+        Ok(())
+    } else {
+        Err(format!("{} answered {}", config.url.trim(), response.status()))
+    }
+}
+
+/// Sends the webhook without blocking the UI thread. Deliveries are logged to `events`,
+/// failures are reported.
+pub fn send(config: Config, event: Event, fields: Fields, events: EventSender) {
+    thread::spawn(move || match send_blocking(&config, event, &fields) {
+        Ok(()) => events.send(Subsystem::Webhook, trf("Sent {} webhook to {}", &[&event.name(), &config.url.trim()])),
+        Err(err) => error::report(format!("Error sending {} webhook", event.name()), err),
+    });
+}
@@ -0,0 +1,74 @@
+use crate::{error, paths};
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+/// Size and position of the main window, stored per profile as JSON next to the confy file
+/// so it is kept without saving the settings.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Geometry {
+    /// Outer top-left corner in points; `None` lets the window manager place it.
+    pub position: Option<[f32; 2]>,
+    /// Inner size in points.
+    pub size: [f32; 2],
+    pub maximized: bool,
+}
+
+impl Geometry {
+    fn path(config_name: Option<&str>) -> Option<PathBuf> {
+        let config_path = paths::config_file(config_name).ok()?;
+        let profile = config_name.unwrap_or("default-config");
+        Some(config_path.parent()?.join(format!("{}.window.json", profile)))
+    }
+
+    /// The geometry saved on the last exit, if any.
+    pub fn load(config_name: Option<&str>) -> Option<Self> {
+        let path = Self::path(config_name)?;
+        let text = fs::read_to_string(&path).ok()?;
+        serde_json::from_str(&text)
+            .map_err(|err| error::report(format!("Error parsing window geometry {}", path.display()), err))
+            .ok()
+    }
+
+    pub fn save(&self, config_name: Option<&str>) -> Result<(), String> {
+        let path = Self::path(config_name).ok_or("Could not determine window geometry path")?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|err| err.to_string())?;
+        }
+        let text = serde_json::to_string_pretty(self).map_err(|err| err.to_string())?;
+        fs::write(&path, text).map_err(|err| format!("{}: {}", path.display(), err))
+    }
+
+    /// Opens the window where it was, at the size it had before being maximized.
+    pub fn apply(&self, builder: egui::ViewportBuilder) -> egui::ViewportBuilder {
+        let builder = builder.with_inner_size(self.size).with_maximized(self.maximized);
+        match self.position {
+            Some(position) => builder.with_position(position),
+            None => builder,
+        }
+    }
+
+    /// Follows the window as it is moved and resized. While it is maximized or minimized
+    /// only that flag changes, so restoring it next time brings back the normal size.
+    pub fn track(previous: Option<Self>, ctx: &egui::Context) -> Option<Self> {
+        ctx.input(|i| {
+            let viewport = i.viewport();
+            if viewport.minimized == Some(true) {
+                return previous;
+            }
+            let maximized = viewport.maximized == Some(true);
+            if maximized || viewport.fullscreen == Some(true) {
+                return previous.map(|geometry| Geometry { maximized, ..geometry });
+            }
+            let Some(inner) = viewport.inner_rect.filter(|rect| rect.area() > 0.0) else {
+                return previous;
+            };
+            Some(Geometry {
+                position: viewport.outer_rect.map(|rect| rect.min.into()).or(previous.and_then(|geometry| geometry.position)),
+                size: inner.size().into(),
+                maximized: false,
+            })
+        })
+    }
+}
@@ -0,0 +1,382 @@
+use crate::{error, paths};
+use crate::unix_now;
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+use reqwest::blocking::Client;
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// Bumped whenever the cache layout changes; older cache files are discarded.
+const CACHE_VERSION: u32 = 3;
+/// Cached metadata older than this is refreshed in the background (but still shown).
+const MAX_AGE_SECS: u64 = 7 * 86400;
+/// How long to wait before asking the API again after a failed lookup.
+const RETRY_SECS: u64 = 300;
+/// Background threads for lookups and thumbnails; a list full of new maps queues up
+/// instead of opening a connection per map at once.
+const WORKERS: usize = 4;
+/// Limit for each API and thumbnail request.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+
+const STEAM_PAGE_URL: &str = "https://steamcommunity.com/sharedfiles/filedetails/?id=";
+const DETAILS_URL: &str = "https://api.steampowered.com/ISteamRemoteStorage/GetPublishedFileDetails/v1/";
+
+/// mod.io game id of Pavlov.
+pub const DEFAULT_MODIO_GAME_ID: u64 = 3959;
+
+/// Where workshop ids are resolved.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Source {
+    /// Steam Workshop (PC); needs no key.
+    Steam,
+    /// mod.io (Quest/shack maps); needs an API key.
+    ModIo { api_key: String, game_id: u64 },
+}
+
+impl Source {
+    /// Cache key of an id, so both sources can be cached side by side.
+    fn key(&self, id: &str) -> String {
+        match self {
+            Source::Steam => format!("steam_{}", id),
+            Source::ModIo { game_id, .. } => format!("modio{}_{}", game_id, id),
+        }
+    }
+}
+
+/// Metadata of one workshop item.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkshopItem {
+    /// Map name; empty if the source does not know the item.
+    pub title: String,
+    pub preview_url: String,
+    /// Web page of the item; empty for sources that derive it from the id.
+    #[serde(default)]
+    pub page_url: String,
+    /// Unix timestamp (seconds) of when the metadata was fetched.
+    pub fetched_at: u64,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct CacheFile {
+    version: u32,
+    items: HashMap<String, WorkshopItem>,
+}
+
+#[derive(Deserialize)]
+struct DetailsResponse {
+    response: DetailsList,
+}
+
+#[derive(Deserialize)]
+struct DetailsList {
+    #[serde(default)]
+    publishedfiledetails: Vec<Details>,
+}
+
+#[derive(Deserialize)]
+struct ModIoMod {
+    name: String,
+    #[serde(default)]
+    profile_url: String,
+    logo: ModIoLogo,
+}
+
+#[derive(Deserialize)]
+struct ModIoLogo {
+    thumb_320x180: String,
+}
+
+#[derive(Deserialize)]
+struct Details {
+    result: i32,
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    preview_url: String,
+}
+
+/// Work for the background threads, by cache key.
+enum Job {
+    /// Look up metadata and download the thumbnail.
+    Fetch { id: String, key: String, source: Source },
+    /// Decode a cached thumbnail.
+    Thumbnail(String),
+}
+
+enum Message {
+    Item(String, Result<WorkshopItem, String>),
+    Thumbnail(String, egui::ColorImage),
+}
+
+/// Read-through cache of workshop map names and thumbnails. Everything is kept on
+/// disk next to the confy file, so lists render from the cache while the API is down;
+/// stale entries are served as-is and refreshed on a background thread.
+pub struct WorkshopCache {
+    source: Source,
+    /// Items by cache key (see `Source::key`).
+    items: HashMap<String, WorkshopItem>,
+    thumbnails: HashMap<String, egui::TextureHandle>,
+    /// Cache keys with a metadata request in flight.
+    pending: HashSet<String>,
+    /// Cache keys whose thumbnail is being loaded.
+    loading_thumbnails: HashSet<String>,
+    /// Last failed lookup per cache key, to avoid hammering a rate-limited API.
+    failed_at: HashMap<String, u64>,
+    jobs: mpsc::Sender<Job>,
+    rx: mpsc::Receiver<Message>,
+}
+
+impl WorkshopCache {
+    /// Cache directory holding `items.json` and the thumbnail files.
+    fn dir() -> Option<PathBuf> {
+        let config_path = paths::config_file(None).ok()?;
+        Some(config_path.parent()?.join("workshop_cache"))
+    }
+
+    fn thumbnail_path(id: &str) -> Option<PathBuf> {
+        Some(Self::dir()?.join(format!("{}.img", id)))
+    }
+
+    /// Loads the cache from disk, starting empty if it is missing, unreadable or outdated.
+    pub fn load() -> Self {
+        let items = Self::dir()
+            .and_then(|dir| fs::read_to_string(dir.join("items.json")).ok())
+            .and_then(|text| serde_json::from_str::<CacheFile>(&text).ok())
+            .filter(|cache| cache.version == CACHE_VERSION)
+            .map(|cache| cache.items)
+            .unwrap_or_default();
+        let (tx, rx) = mpsc::channel();
+        let (jobs, job_rx) = mpsc::channel();
+        let job_rx = Arc::new(Mutex::new(job_rx));
+        for _ in 0..WORKERS {
+            let (job_rx, tx) = (job_rx.clone(), tx.clone());
+            thread::spawn(move || work(&job_rx, &tx));
+        }
+        Self {
+            source: Source::Steam,
+            items,
+            thumbnails: HashMap::new(),
+            pending: HashSet::new(),
+            loading_thumbnails: HashSet::new(),
+            failed_at: HashMap::new(),
+            jobs,
+            rx,
+        }
+    }
+
+    fn save(&self) {
+        let Some(dir) = Self::dir() else {
+            return;
+        };
+        let cache = CacheFile { version: CACHE_VERSION, items: self.items.clone() };
+        let result = fs::create_dir_all(&dir)
+            .map_err(|err| err.to_string())
+            .and_then(|_| serde_json::to_string(&cache).map_err(|err| err.to_string()))
+            .and_then(|text| fs::write(dir.join("items.json"), text).map_err(|err| err.to_string()));
+        if let Err(err) = result {
+            error::report("Error saving workshop cache", err);
+        }
+    }
+
+    /// Switches the API used for lookups. Items resolved through the other source stay cached.
+    pub fn set_source(&mut self, source: Source) {
+        if source != self.source {
+            self.source = source;
+            self.failed_at.clear();
+        }
+    }
+
+    /// Metadata for a workshop id, queueing a background refresh when it is missing or stale.
+    pub fn get(&mut self, id: &str) -> Option<&WorkshopItem> {
+        if id.is_empty() || !id.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        if matches!(&self.source, Source::ModIo { api_key, .. } if api_key.is_empty()) {
+            return None;
+        }
+        let key = self.source.key(id);
+        let now = unix_now();
+        let stale = self.items.get(&key).map_or(true, |item| now.saturating_sub(item.fetched_at) > MAX_AGE_SECS);
+        let backing_off = self.failed_at.get(&key).is_some_and(|t| now.saturating_sub(*t) < RETRY_SECS);
+        if stale && !backing_off && !self.pending.contains(&key) {
+            self.pending.insert(key.clone());
+            let _ = self.jobs.send(Job::Fetch { id: id.to_owned(), key: key.clone(), source: self.source.clone() });
+        }
+        self.items.get(&key)
+    }
+
+    /// Map name for a workshop id, falling back to the id itself.
+    pub fn title(&mut self, id: &str) -> String {
+        match self.get(id) {
+            Some(item) if !item.title.is_empty() => item.title.clone(),
+            _ => id.to_owned(),
+        }
+    }
+
+    /// Web page of a workshop id. Steam pages follow from the id; mod.io pages are only
+    /// known once the item has been fetched.
+    pub fn page_url(&mut self, id: &str) -> Option<String> {
+        match self.source {
+            Source::Steam if !id.is_empty() && id.bytes().all(|b| b.is_ascii_digit()) => {
+                Some(format!("{}{}", STEAM_PAGE_URL, id))
+            }
+            Source::Steam => None,
+            Source::ModIo { .. } => self.get(id).map(|item| item.page_url.clone()).filter(|url| !url.is_empty()),
+        }
+    }
+
+    /// Thumbnail texture for a workshop id, decoded from the disk cache in the background.
+    pub fn thumbnail(&mut self, id: &str) -> Option<&egui::TextureHandle> {
+        self.get(id)?;
+        let key = self.source.key(id);
+        if !self.thumbnails.contains_key(&key) && !self.loading_thumbnails.contains(&key) {
+            self.loading_thumbnails.insert(key.clone());
+            let _ = self.jobs.send(Job::Thumbnail(key.clone()));
+        }
+        self.thumbnails.get(&key)
+    }
+
+    /// Applies finished background work. Call once per frame.
+    pub fn poll(&mut self, ctx: &egui::Context) {
+        let mut changed = false;
+        while let Ok(message) = self.rx.try_recv() {
+            match message {
+                Message::Item(id, Ok(item)) => {
+                    self.pending.remove(&id);
+                    self.failed_at.remove(&id);
+                    // The thumbnail file may have changed with the metadata.
+                    self.thumbnails.remove(&id);
+                    self.loading_thumbnails.remove(&id);
+                    self.items.insert(id, item);
+                    changed = true;
+                }
+                Message::Item(id, Err(err)) => {
+                    error::report(format!("Error fetching workshop item {}", id), err);
+                    self.pending.remove(&id);
+                    self.failed_at.insert(id, unix_now());
+                }
+                Message::Thumbnail(id, image) => {
+                    let texture = ctx.load_texture(format!("workshop_{}", id), image, egui::TextureOptions::LINEAR);
+                    self.thumbnails.insert(id, texture);
+                }
+            }
+        }
+        if changed {
+            self.save();
+        }
+    }
+}
+
+/// Runs jobs from the queue until the cache is dropped.
+fn work(jobs: &Mutex<mpsc::Receiver<Job>>, tx: &mpsc::Sender<Message>) {
+    loop {
+        // The lock is released before the job runs, so the other threads can take the next one.
+        let job = jobs.lock().unwrap().recv();
+        match job {
+            Ok(Job::Fetch { id, key, source }) => {
+                let result = match &source {
+                    Source::Steam => fetch_item(&id),
+                    Source::ModIo { api_key, game_id } => fetch_modio_item(&id, api_key, *game_id),
+                };
+                if let Ok(item) = &result {
+                    download_thumbnail(&key, &item.preview_url);
+                }
+                let _ = tx.send(Message::Item(key, result));
+            }
+            Ok(Job::Thumbnail(key)) => {
+                if let Some(image) = WorkshopCache::thumbnail_path(&key).and_then(|p| fs::read(p).ok()).and_then(|b| decode(&b)) {
+                    let _ = tx.send(Message::Thumbnail(key, image));
+                }
+            }
+            Err(_) => return,
+        }
+    }
+}
+
+/// Client for the workshop APIs and thumbnail hosts.
+fn client() -> Result<Client, String> {
+    Client::builder().timeout(REQUEST_TIMEOUT).build().map_err(|err| err.to_string())
+}
+
+fn fetch_item(id: &str) -> Result<WorkshopItem, String> {
+    let client = client()?;
+    let response: DetailsResponse = client
+        .post(DETAILS_URL)
+        .form(&[("itemcount", "1"), ("publishedfileids[0]", id)])
+        .send()
+        .and_then(|resp| resp.error_for_status())
+        .and_then(|resp| resp.json())
+        .map_err(|err| err.to_string())?;
+    let details = response.response.publishedfiledetails.into_iter().find(|d| d.result == 1);
+    Ok(WorkshopItem {
+        title: details.as_ref().map(|d| d.title.clone()).unwrap_or_default(),
+        preview_url: details.map(|d| d.preview_url).unwrap_or_default(),
+        page_url: String::new(),
+        fetched_at: unix_now(),
+    })
+}
+
+fn fetch_modio_item(id: &str, api_key: &str, game_id: u64) -> Result<WorkshopItem, String> {
+    let client = client()?;
+    let url = format!("https://api.mod.io/v1/games/{}/mods/{}", game_id, id);
+    let response = client
+        .get(&url)
+        .query(&[("api_key", api_key)])
+        .send()
+        .map_err(|err| err.to_string())?;
+    // Unknown ids are cached as such, like Steam items without a result.
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(WorkshopItem {
+            title: String::new(),
+            preview_url: String::new(),
+            page_url: String::new(),
+            fetched_at: unix_now(),
+        });
+    }
+    let item: ModIoMod = response
+        .error_for_status()
+        .and_then(|resp| resp.json())
+        .map_err(|err| err.to_string())?;
+    Ok(WorkshopItem {
+        title: item.name,
+        preview_url: item.logo.thumb_320x180,
+        page_url: item.profile_url,
+        fetched_at: unix_now(),
+    })
+}
+
+/// Stores the preview image on disk. Failures keep whatever thumbnail was cached before.
+fn download_thumbnail(id: &str, url: &str) {
+    let (Some(path), false) = (WorkshopCache::thumbnail_path(id), url.is_empty()) else {
+        return;
+    };
+    let bytes = client().and_then(|client| {
+        client
+            .get(url)
+            .send()
+            .and_then(|resp| resp.error_for_status())
+            .and_then(|resp| resp.bytes())
+            .map_err(|err| err.to_string())
+    });
+    match bytes {
+        Ok(bytes) => {
+            if let Some(dir) = path.parent() {
+                let _ = fs::create_dir_all(dir);
+            }
+            if let Err(err) = fs::write(&path, &bytes) {
+                error::report(format!("Error caching workshop thumbnail {}", id), err);
+            }
+        }
+        Err(err) => error::report(format!("Error downloading workshop thumbnail {}", id), err),
+    }
+}
+
+fn decode(bytes: &[u8]) -> Option<egui::ColorImage> {
+    let img = image::load_from_memory(bytes).ok()?.to_rgba8();
+    let size = [img.width() as usize, img.height() as usize];
+    Some(egui::ColorImage::from_rgba_unmultiplied(size, &img.into_raw()))
+}